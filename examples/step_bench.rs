@@ -0,0 +1,80 @@
+//! Measures how long a rapier step takes as the shapes in the window grow from 10 to 5000,
+//! running the plugin headless, and prints the averages as CSV:
+//!
+//! ```text
+//! cargo run --release --example step_bench > steps.csv
+//! ```
+//!
+//! The shapes are spawned by the plugin itself, with the same sizes and collision groups as in the
+//! app, so they start out piled on top of each other once there are many. The first steps, while
+//! they push apart, aren't measured.
+
+use bevy::{asset::AssetPlugin, input::InputPlugin, prelude::*, window::WindowPlugin};
+use bevy_rapier2d::{
+    physics::{PhysicsSystems, TimestepMode},
+    prelude::*,
+};
+use window_dot_velocity::{Headless, WindowPhysicsPlugin};
+
+const SHAPE_COUNTS: [usize; 7] = [10, 50, 100, 500, 1000, 2000, 5000];
+const WARM_UP_STEPS: u32 = 120;
+const MEASURED_STEPS: u32 = 300;
+
+// rapier's own timing of its steps, which leaves out the systems running alongside them
+#[derive(Default)]
+struct StepTimes {
+    total_ms: f64,
+    steps: u32,
+}
+
+fn record_step_time(pipeline: Res<PhysicsPipeline>, mut times: ResMut<StepTimes>) {
+    times.total_ms += pipeline.counters.step_time();
+    times.steps += 1;
+}
+
+fn step_app(shapes: usize) -> App {
+    let mut app = App::new();
+    app.insert_resource(Headless {
+        monitor_size: [3840., 2160.],
+        window_size: [1920., 1080.],
+    })
+    // no renderer, so it runs without a GPU. No logger either, a fresh app is built for every
+    // shape count and the global logger can only be set once
+    .add_plugins(MinimalPlugins)
+    .add_plugin(WindowPlugin::default())
+    .add_plugin(InputPlugin)
+    .add_plugin(AssetPlugin)
+    .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+    .add_plugin(WindowPhysicsPlugin::new().with_shape_count(shapes))
+    .init_resource::<StepTimes>()
+    .add_system(record_step_time.after(PhysicsSystems::StepWorld));
+    app.world
+        .get_resource_mut::<PhysicsPipeline>()
+        .unwrap()
+        .counters
+        .enable();
+    // one step every update however fast they come, rather than one per 1/60 s of real time
+    app.world
+        .get_resource_mut::<RapierConfiguration>()
+        .unwrap()
+        .timestep_mode = TimestepMode::FixedTimestep;
+    app
+}
+
+fn main() {
+    println!("shapes,steps,mean_step_ms");
+    for shapes in SHAPE_COUNTS {
+        let mut app = step_app(shapes);
+        for _ in 0..WARM_UP_STEPS {
+            app.update();
+        }
+        *app.world.get_resource_mut::<StepTimes>().unwrap() = StepTimes::default();
+        for _ in 0..MEASURED_STEPS {
+            app.update();
+        }
+
+        let times = app.world.get_resource::<StepTimes>().unwrap();
+        let mean = times.total_ms / times.steps.max(1) as f64;
+        println!("{},{},{:.3}", shapes, times.steps, mean);
+    }
+}
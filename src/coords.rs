@@ -0,0 +1,551 @@
+use bevy::{prelude::*, window::WindowId, winit::WinitWindows};
+use bevy_rapier2d::prelude::*;
+use winit::{
+    dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize},
+    monitor::MonitorHandle,
+};
+
+pub(crate) fn box_collider([hx, hy]: [Real; 2]) -> ColliderShape {
+    ColliderShape::compound(
+        [[1., 0.], [0., 1.], [-1., 0.], [0., -1.]]
+            .map(|v| {
+                let v: nalgebra::Unit<Vector<_>> = nalgebra::Unit::new_unchecked(v.into());
+                (
+                    Isometry::new(-v.component_mul(&Vector::from([hx, hy])), 0.0),
+                    ColliderShape::halfspace(v),
+                )
+            })
+            .into(),
+    )
+}
+
+// slabs along the parts of a monitor's sides that don't border another monitor, so the window
+// can't get into the gaps of a desktop made of monitors of different sizes. A slab only reaches
+// round a corner of the monitor that is a corner of the desktop too, where it would otherwise leave
+// a gap, since past any other corner it would stick into a neighbouring monitor
+pub(crate) fn monitor_collider(
+    monitor: &MonitorRect,
+    monitors: &[MonitorRect],
+    converter: &CoordConverter,
+) -> Option<ColliderShape> {
+    let [hx, hy]: [Real; 2] = (converter.to_physics_vec(monitor.size) / 2.).into();
+    let t = MONITOR_WALL_THICKNESS / 2.;
+    let center = monitor.center();
+    let scale = |length: Real| length / converter.physics_scale;
+    let mut slabs = Vec::new();
+    for side in [Side::Left, Side::Right, Side::Top, Side::Bottom] {
+        let [lo, hi] = monitor.extent(side);
+        // the sides meeting this one at its ends, and where along them it is
+        let (lo_side, hi_side, at) = match side {
+            Side::Left => (Side::Top, Side::Bottom, monitor.left()),
+            Side::Right => (Side::Top, Side::Bottom, monitor.right()),
+            Side::Top => (Side::Left, Side::Right, monitor.top()),
+            Side::Bottom => (Side::Left, Side::Right, monitor.bottom()),
+        };
+        for [a, b] in monitor.open_spans(side, monitors) {
+            let corner = |span_end: Real, bound: Real, perpendicular: Side| {
+                if span_end == bound && monitor.is_open_at(perpendicular, at, monitors) {
+                    2. * t
+                } else {
+                    0.
+                }
+            };
+            let (start, end) = (corner(a, lo, lo_side), corner(b, hi, hi_side));
+            let (offset, half_extents) = match side {
+                // desktop y points down, physics y up
+                Side::Left | Side::Right => {
+                    let top = scale(center.y - a) + start;
+                    let bottom = scale(center.y - b) - end;
+                    let x = if let Side::Left = side {
+                        -(hx + t)
+                    } else {
+                        hx + t
+                    };
+                    ([x, (top + bottom) / 2.], [t, (top - bottom) / 2.])
+                }
+                Side::Top | Side::Bottom => {
+                    let left = scale(a - center.x) - start;
+                    let right = scale(b - center.x) + end;
+                    let y = if let Side::Top = side {
+                        hy + t
+                    } else {
+                        -(hy + t)
+                    };
+                    ([(left + right) / 2., y], [(right - left) / 2., t])
+                }
+            };
+            slabs.push((
+                Isometry::new(offset.into(), 0.),
+                ColliderShape::cuboid(half_extents[0], half_extents[1]),
+            ));
+        }
+    }
+    (!slabs.is_empty()).then(|| ColliderShape::compound(slabs))
+}
+
+// thickness of the slabs around each monitor, in physics units
+const MONITOR_WALL_THICKNESS: Real = 1.;
+
+#[derive(Clone, Copy)]
+pub(crate) enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct MonitorRect {
+    pub(crate) origin: LogicalPosition<Real>, // top left, in desktop units
+    pub(crate) size: LogicalSize<Real>,
+}
+
+#[cfg(windows)]
+fn work_area(monitor: &MonitorHandle) -> Option<(PhysicalPosition<i32>, PhysicalSize<u32>)> {
+    use winapi::um::winuser::{GetMonitorInfoW, MONITORINFO};
+    use winit::platform::windows::MonitorHandleExtWindows;
+
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..unsafe { std::mem::zeroed() }
+    };
+    // SAFETY: the handle comes from winit and info is sized for the call
+    if unsafe { GetMonitorInfoW(monitor.hmonitor() as _, &mut info) } == 0 {
+        return None;
+    }
+    let work = info.rcWork;
+    Some((
+        PhysicalPosition::new(work.left, work.top),
+        PhysicalSize::new(
+            (work.right - work.left) as u32,
+            (work.bottom - work.top) as u32,
+        ),
+    ))
+}
+
+// winit doesn't report the work area, so elsewhere the window can still end up under a dock or,
+// on macOS, partly behind the menu bar at the top of the monitor
+#[cfg(not(windows))]
+fn work_area(_monitor: &MonitorHandle) -> Option<(PhysicalPosition<i32>, PhysicalSize<u32>)> {
+    None
+}
+
+impl MonitorRect {
+    // the work area, which leaves out the taskbar, where the platform reports it
+    pub(crate) fn new(monitor: &MonitorHandle, desktop_scale: f64) -> Self {
+        let (origin, size) = work_area(monitor).unwrap_or((monitor.position(), monitor.size()));
+        Self {
+            origin: origin.to_logical(desktop_scale),
+            size: size.to_logical(desktop_scale),
+        }
+    }
+
+    pub(crate) fn left(&self) -> Real {
+        self.origin.x
+    }
+
+    pub(crate) fn right(&self) -> Real {
+        self.origin.x + self.size.width
+    }
+
+    pub(crate) fn top(&self) -> Real {
+        self.origin.y
+    }
+
+    pub(crate) fn bottom(&self) -> Real {
+        self.origin.y + self.size.height
+    }
+
+    pub(crate) fn center(&self) -> LogicalPosition<Real> {
+        LogicalPosition::new(
+            self.origin.x + self.size.width / 2.,
+            self.origin.y + self.size.height / 2.,
+        )
+    }
+
+    /// whether no other monitor borders `side` of this one, so the window can't cross it
+    pub(crate) fn is_open(&self, side: Side, monitors: &[MonitorRect]) -> bool {
+        !monitors.iter().any(|other| self.touches(other, side))
+    }
+
+    /// start and end of `side`, along it in desktop units
+    fn extent(&self, side: Side) -> [Real; 2] {
+        match side {
+            Side::Left | Side::Right => [self.top(), self.bottom()],
+            Side::Top | Side::Bottom => [self.left(), self.right()],
+        }
+    }
+
+    /// the stretches of `side` that no other monitor borders, along it in desktop units
+    pub(crate) fn open_spans(&self, side: Side, monitors: &[MonitorRect]) -> Vec<[Real; 2]> {
+        monitors
+            .iter()
+            .filter(|other| self.touches(other, side))
+            .fold(vec![self.extent(side)], |spans, other| {
+                let [covered_from, covered_to] = other.extent(side);
+                spans
+                    .into_iter()
+                    .flat_map(|[a, b]| [[a, b.min(covered_from)], [a.max(covered_to), b]])
+                    .filter(|[a, b]| a < b)
+                    .collect()
+            })
+    }
+
+    /// whether the point `at` along `side` is on an open stretch of it
+    fn is_open_at(&self, side: Side, at: Real, monitors: &[MonitorRect]) -> bool {
+        self.open_spans(side, monitors)
+            .iter()
+            .any(|&[a, b]| a <= at && at <= b)
+    }
+
+    /// whether `other` sits flush against `side` of this monitor
+    fn touches(&self, other: &MonitorRect, side: Side) -> bool {
+        const EPSILON: Real = 1.;
+        let overlaps = |a: [Real; 2], b: [Real; 2]| a[0] < b[1] && b[0] < a[1];
+        let vertical = overlaps([self.top(), self.bottom()], [other.top(), other.bottom()]);
+        let horizontal = overlaps([self.left(), self.right()], [other.left(), other.right()]);
+        match side {
+            Side::Left => vertical && (self.left() - other.right()).abs() < EPSILON,
+            Side::Right => vertical && (self.right() - other.left()).abs() < EPSILON,
+            Side::Top => horizontal && (self.top() - other.bottom()).abs() < EPSILON,
+            Side::Bottom => horizontal && (self.bottom() - other.top()).abs() < EPSILON,
+        }
+    }
+}
+
+/// Converts between winit's desktop coordinates (y down) and physics coordinates (y up).
+///
+/// Desktop positions are measured in physical pixels divided by `desktop_scale`, rather than by
+/// the scale factor of whichever monitor the window is on, so a point keeps the same desktop
+/// position while the window crosses between monitors with different scale factors.
+#[derive(Clone)]
+pub(crate) struct CoordConverter {
+    pub(crate) monitors: Vec<MonitorRect>,
+    pub(crate) desktop_scale: f64,
+    pub(crate) physics_scale: Real,
+}
+
+impl CoordConverter {
+    /// measures the monitors around `window`, None while it isn't on any
+    pub(crate) fn new(window: &winit::window::Window, physics_scale: Real) -> Option<Self> {
+        let desktop_scale = window.current_monitor()?.scale_factor();
+        Some(Self::measure(window, desktop_scale, physics_scale))
+    }
+
+    /// measures the monitors again, e.g. after one was plugged in, keeping the desktop scale and
+    /// with it the size of everything in physics. None while `window` isn't on any monitor
+    pub(crate) fn remeasure(&self, window: &winit::window::Window) -> Option<Self> {
+        window.current_monitor()?;
+        Some(Self::measure(
+            window,
+            self.desktop_scale,
+            self.physics_scale,
+        ))
+    }
+
+    fn measure(window: &winit::window::Window, desktop_scale: f64, physics_scale: Real) -> Self {
+        let monitors = window
+            .available_monitors()
+            .map(|m| MonitorRect::new(&m, desktop_scale))
+            .collect();
+        Self {
+            monitors,
+            desktop_scale,
+            physics_scale,
+        }
+    }
+
+    /// a desktop of one monitor of `size` at the origin, for running without OS windows
+    pub(crate) fn headless(size: LogicalSize<Real>, physics_scale: Real) -> Self {
+        Self {
+            monitors: vec![MonitorRect {
+                origin: LogicalPosition::new(0., 0.),
+                size,
+            }],
+            desktop_scale: 1.,
+            physics_scale,
+        }
+    }
+
+    fn desktop_bottom(&self) -> Real {
+        self.monitors
+            .iter()
+            .map(MonitorRect::bottom)
+            .fold(Real::NEG_INFINITY, Real::max)
+    }
+
+    pub(crate) fn from_physical(&self, p: PhysicalPosition<i32>) -> LogicalPosition<Real> {
+        p.to_logical(self.desktop_scale)
+    }
+
+    pub(crate) fn from_physical_size(&self, s: PhysicalSize<u32>) -> LogicalSize<Real> {
+        s.to_logical(self.desktop_scale)
+    }
+
+    pub(crate) fn to_physical(&self, p: LogicalPosition<Real>) -> PhysicalPosition<i32> {
+        p.to_physical(self.desktop_scale)
+    }
+
+    /// bottom left and top right corners of the box around all monitors, in physics units
+    pub(crate) fn desktop_physics_bounds(&self) -> [Point<Real>; 2] {
+        let left = self
+            .monitors
+            .iter()
+            .map(MonitorRect::left)
+            .fold(Real::INFINITY, Real::min);
+        let right = self
+            .monitors
+            .iter()
+            .map(MonitorRect::right)
+            .fold(Real::NEG_INFINITY, Real::max);
+        let top = self
+            .monitors
+            .iter()
+            .map(MonitorRect::top)
+            .fold(Real::INFINITY, Real::min);
+        [
+            self.to_physics_point(LogicalPosition::new(left, self.desktop_bottom())),
+            self.to_physics_point(LogicalPosition::new(right, top)),
+        ]
+    }
+
+    /// `top_left` of a window of `size`, moved as little as possible so that at least `margin` of
+    /// it overlaps a monitor across and down, all in desktop units
+    pub(crate) fn keep_on_screen(
+        &self,
+        top_left: LogicalPosition<Real>,
+        size: LogicalSize<Real>,
+        margin: Real,
+    ) -> LogicalPosition<Real> {
+        let (margin_x, margin_y) = (margin.min(size.width), margin.min(size.height));
+        self.monitors
+            .iter()
+            .map(|monitor| {
+                LogicalPosition::new(
+                    top_left
+                        .x
+                        .max(monitor.left() - size.width + margin_x)
+                        .min(monitor.right() - margin_x),
+                    top_left
+                        .y
+                        .max(monitor.top() - size.height + margin_y)
+                        .min(monitor.bottom() - margin_y),
+                )
+            })
+            .min_by(|a, b| {
+                let distance = |p: &LogicalPosition<Real>| {
+                    (p.x - top_left.x).powi(2) + (p.y - top_left.y).powi(2)
+                };
+                distance(a).total_cmp(&distance(b))
+            })
+            .unwrap_or(top_left)
+    }
+
+    /// bottom left and top right corners of `monitor`, in physics units
+    pub(crate) fn monitor_physics_bounds(&self, monitor: &MonitorRect) -> [Point<Real>; 2] {
+        [
+            self.to_physics_point(LogicalPosition::new(monitor.left(), monitor.bottom())),
+            self.to_physics_point(LogicalPosition::new(monitor.right(), monitor.top())),
+        ]
+    }
+
+    fn flip(&self, mut p: LogicalPosition<Real>) -> LogicalPosition<Real> {
+        p.y = self.desktop_bottom() - p.y;
+        p
+    }
+
+    pub(crate) fn to_physics_point(&self, p: LogicalPosition<Real>) -> Point<Real> {
+        self.to_physics_vec(<[Real; 2]>::from(self.flip(p)).into())
+            .into()
+    }
+
+    pub(crate) fn to_physics_vec(&self, p: LogicalSize<Real>) -> Vector<Real> {
+        Vector::from(<[_; 2]>::from(p)) / self.physics_scale
+    }
+
+    pub(crate) fn to_logical_winit_position(&self, v: Point<Real>) -> LogicalPosition<Real> {
+        self.flip(<[Real; 2]>::from(self.to_logical_size(v.coords)).into())
+    }
+
+    fn to_logical_size(&self, v: Vector<Real>) -> LogicalSize<Real> {
+        <[_; 2]>::from(v * self.physics_scale).into()
+    }
+
+    /// the cursor as bevy reports it, in the window's logical pixels from the bottom left of the
+    /// content area, in desktop units along with its offset from the top left of the content area
+    pub(crate) fn cursor_on_desktop(
+        &self,
+        cursor: Vec2,
+        window_height: f32,
+        window_scale: f64,
+        content_top_left: PhysicalPosition<i32>,
+    ) -> (LogicalPosition<Real>, LogicalPosition<Real>) {
+        let top_left = self.from_physical(content_top_left);
+        // the window's scale factor can differ from the one desktop units are measured in
+        let scale = (window_scale / self.desktop_scale) as Real;
+        let offset = LogicalPosition::new(cursor.x * scale, (window_height - cursor.y) * scale);
+        (
+            LogicalPosition::new(top_left.x + offset.x, top_left.y + offset.y),
+            offset,
+        )
+    }
+}
+
+/// Where the cursor is over the window `id`, in desktop units, and its offset from the top left of
+/// the content area.
+pub(crate) fn cursor_on_desktop(
+    windows: &Windows,
+    winit_windows: &WinitWindows,
+    id: WindowId,
+    converter: &CoordConverter,
+) -> Option<(LogicalPosition<Real>, LogicalPosition<Real>)> {
+    let window = windows.get(id)?;
+    let cursor = window.cursor_position()?;
+    let top_left = winit_windows.get_window(id)?.inner_position().ok()?;
+    Some(converter.cursor_on_desktop(cursor, window.height(), window.scale_factor(), top_left))
+}
+
+/// Where the cursor is over the window `id`, in physics units.
+pub(crate) fn cursor_to_physics(
+    windows: &Windows,
+    winit_windows: &WinitWindows,
+    id: WindowId,
+    converter: &CoordConverter,
+) -> Option<Point<Real>> {
+    let (p, _) = cursor_on_desktop(windows, winit_windows, id, converter)?;
+    Some(converter.to_physics_point(p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converter(monitors: &[([Real; 2], [Real; 2])]) -> CoordConverter {
+        CoordConverter {
+            monitors: monitors
+                .iter()
+                .map(|&([x, y], [width, height])| MonitorRect {
+                    origin: LogicalPosition::new(x, y),
+                    size: LogicalSize::new(width, height),
+                })
+                .collect(),
+            desktop_scale: 1.,
+            physics_scale: 1500.,
+        }
+    }
+
+    fn assert_round_trips(converter: &CoordConverter, points: &[[Real; 2]]) {
+        for &[x, y] in points {
+            let p = LogicalPosition::new(x, y);
+            let back = converter.to_logical_winit_position(converter.to_physics_point(p));
+            assert!(
+                (back.x - p.x).abs() < 1e-3 && (back.y - p.y).abs() < 1e-3,
+                "{:?} came back as {:?}",
+                p,
+                back
+            );
+        }
+    }
+
+    #[test]
+    fn single_monitor_round_trips() {
+        let converter = converter(&[([0., 0.], [1920., 1080.])]);
+        assert_round_trips(
+            &converter,
+            &[
+                [0., 0.],
+                [1920., 0.],
+                [0., 1080.],
+                [1920., 1080.],
+                [960., 540.],
+                [-200., -50.],
+                [2500., 1300.],
+            ],
+        );
+    }
+
+    #[test]
+    fn monitors_left_of_and_above_the_primary_round_trip() {
+        let converter = converter(&[
+            ([0., 0.], [1920., 1080.]),
+            ([-1280., -300.], [1280., 1024.]),
+        ]);
+        assert_round_trips(
+            &converter,
+            &[[-1280., -300.], [-1., 723.], [0., 1080.], [500., -300.]],
+        );
+    }
+
+    #[test]
+    fn physics_y_points_up() {
+        let converter = converter(&[([0., 0.], [1920., 1080.])]);
+        let bottom = converter.to_physics_point(LogicalPosition::new(0., 1080.));
+        let top = converter.to_physics_point(LogicalPosition::new(0., 0.));
+        assert!(bottom.y.abs() < 1e-6);
+        assert!((top.y - 1080. / 1500.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cursor_on_a_high_dpi_window_is_in_desktop_units() {
+        let converter = converter(&[([0., 0.], [1920., 1080.])]);
+        // a window with a scale factor of 2 on a desktop measured at 1
+        let (p, offset) = converter.cursor_on_desktop(
+            Vec2::new(50., 350.),
+            400.,
+            2.,
+            PhysicalPosition::new(100, 100),
+        );
+        assert!((offset.x - 100.).abs() < 1e-6 && (offset.y - 100.).abs() < 1e-6);
+        assert!((p.x - 200.).abs() < 1e-6 && (p.y - 200.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cursor_on_a_desktop_measured_at_2_is_in_desktop_units() {
+        let mut converter = converter(&[([0., 0.], [960., 540.])]);
+        converter.desktop_scale = 2.;
+        let (p, offset) = converter.cursor_on_desktop(
+            Vec2::new(50., 350.),
+            400.,
+            2.,
+            PhysicalPosition::new(100, 100),
+        );
+        assert!((offset.x - 50.).abs() < 1e-6 && (offset.y - 50.).abs() < 1e-6);
+        assert!((p.x - 100.).abs() < 1e-6 && (p.y - 100.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cursor_moving_up_the_screen_moves_up_in_physics() {
+        let converter = converter(&[([0., 0.], [1920., 1080.])]);
+        let top_left = PhysicalPosition::new(100, 100);
+        let (low, _) = converter.cursor_on_desktop(Vec2::new(50., 100.), 400., 2., top_left);
+        let (high, _) = converter.cursor_on_desktop(Vec2::new(50., 300.), 400., 2., top_left);
+        assert!(converter.to_physics_point(high).y > converter.to_physics_point(low).y);
+    }
+
+    #[test]
+    fn shorter_neighbour_leaves_the_rest_of_the_side_open() {
+        let converter = converter(&[([0., 0.], [1920., 1080.]), ([1920., 0.], [1280., 1024.])]);
+        let [big, small] = [&converter.monitors[0], &converter.monitors[1]];
+        assert_eq!(
+            big.open_spans(Side::Right, &converter.monitors),
+            vec![[1024., 1080.]]
+        );
+        assert!(small.open_spans(Side::Left, &converter.monitors).is_empty());
+        assert_eq!(
+            small.open_spans(Side::Bottom, &converter.monitors),
+            vec![[1920., 3200.]]
+        );
+    }
+
+    #[test]
+    fn lost_window_is_kept_on_screen() {
+        let converter = converter(&[([0., 0.], [1920., 1080.]), ([1920., 0.], [1920., 1080.])]);
+        let size = LogicalSize::new(600., 400.);
+
+        let inside = LogicalPosition::new(1700., 300.);
+        assert_eq!(converter.keep_on_screen(inside, size, 50.), inside);
+
+        let lost = converter.keep_on_screen(LogicalPosition::new(5000., -2000.), size, 50.);
+        assert_eq!(lost, LogicalPosition::new(3790., -350.));
+    }
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::coords::CoordConverter;
+
+/// Outlines of the window bodies' and shapes' colliders drawn over everything, toggled with F1. Off
+/// by default.
+#[derive(Default)]
+pub struct DebugRender(pub bool);
+
+/// Outline of its parent's collider.
+#[derive(Component)]
+pub(crate) struct ColliderOutline;
+
+// segments a ball's outline is drawn with
+const BALL_SEGMENTS: usize = 24;
+
+pub(crate) fn f1_toggles_debug_render(
+    keys: Res<Input<KeyCode>>,
+    mut debug_render: ResMut<DebugRender>,
+) {
+    if keys.just_pressed(KeyCode::F1) {
+        debug_render.0 = !debug_render.0;
+    }
+}
+
+// only colliders with a transform are outlined, the outline is its child. The outlines are all
+// redrawn when a collider is added or changes, which is fine for debugging
+pub(crate) fn draw_collider_outlines(
+    mut commands: Commands,
+    debug_render: Res<DebugRender>,
+    converter: Res<CoordConverter>,
+    colliders: Query<(Entity, &ColliderShapeComponent), With<Transform>>,
+    changed: Query<Entity, (Changed<ColliderShapeComponent>, With<Transform>)>,
+    outlines: Query<Entity, With<ColliderOutline>>,
+) {
+    if !debug_render.is_changed() && changed.iter().next().is_none() {
+        return;
+    }
+    for outline in outlines.iter() {
+        commands.entity(outline).despawn();
+    }
+    if !debug_render.0 {
+        return;
+    }
+
+    for (entity, shape) in colliders.iter() {
+        let mut builder = PathBuilder::new();
+        outline_shape(&mut builder, &***shape, &Isometry::identity(), &converter);
+        let outline = commands
+            .spawn_bundle(GeometryBuilder::build_as(
+                &builder.build().0,
+                DrawMode::Stroke(StrokeMode::new(Color::LIME_GREEN, 1.)),
+                // in front of the shapes and their trails
+                Transform::from_xyz(0., 0., 10.),
+            ))
+            .insert(ColliderOutline)
+            .id();
+        commands.entity(entity).add_child(outline);
+    }
+}
+
+fn outline_shape(
+    builder: &mut PathBuilder,
+    shape: &dyn Shape,
+    isometry: &Isometry<Real>,
+    converter: &CoordConverter,
+) {
+    let points: Vec<Point<Real>> = if let Some(compound) = shape.as_compound() {
+        for (part_isometry, part) in compound.shapes() {
+            outline_shape(builder, &**part, &(isometry * part_isometry), converter);
+        }
+        return;
+    } else if let Some(cuboid) = shape.as_cuboid() {
+        let [hx, hy] = [cuboid.half_extents.x, cuboid.half_extents.y];
+        vec![
+            Point::new(-hx, -hy),
+            Point::new(hx, -hy),
+            Point::new(hx, hy),
+            Point::new(-hx, hy),
+        ]
+    } else if let Some(ball) = shape.as_ball() {
+        (0..BALL_SEGMENTS)
+            .map(|i| {
+                let angle = i as Real * std::f32::consts::TAU / BALL_SEGMENTS as Real;
+                Point::new(angle.cos(), angle.sin()) * ball.radius
+            })
+            .collect()
+    } else if let Some(polygon) = shape.as_convex_polygon() {
+        polygon.points().to_vec()
+    } else if let Some(triangle) = shape.as_triangle() {
+        triangle.vertices().to_vec()
+    } else {
+        return;
+    };
+
+    let to_pixels = |p: &Point<Real>| {
+        let p = isometry * p;
+        Vec2::new(p.x, p.y) * converter.physics_scale
+    };
+    builder.move_to(to_pixels(&points[0]));
+    for p in &points[1..] {
+        builder.line_to(to_pixels(p));
+    }
+    builder.close();
+}
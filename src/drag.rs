@@ -0,0 +1,627 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, winit::WinitWindows};
+use bevy_prototype_lyon::prelude::*;
+use bevy_rapier2d::prelude::*;
+use winit::dpi::{LogicalPosition, PhysicalPosition};
+
+use crate::{
+    coords::{cursor_on_desktop, cursor_to_physics, CoordConverter},
+    shapes::InteriorShape,
+    simulation::{TimeScale, PHYSICS_DT},
+    PhysicsWindow, PrimaryWindow, WindowState,
+};
+
+// seconds of recent cursor samples the release velocity is computed from, five physics steps.
+// Counting samples instead would measure a shorter, jumpier stretch on faster monitors
+const DRAG_WINDOW: f64 = 5. * PHYSICS_DT as f64;
+
+/// Recent cursor positions while dragging, oldest first, with their time in seconds.
+#[derive(Component, Default)]
+pub(crate) struct DragHistory(VecDeque<(f64, LogicalPosition<Real>)>);
+
+impl DragHistory {
+    fn push(&mut self, time: f64, p: LogicalPosition<Real>) {
+        self.0.push_back((time, p));
+        // two samples are kept however old, the velocity needs both ends
+        while self.0.len() > 2
+            && self
+                .0
+                .front()
+                .map_or(false, |&(t, _)| time - t > DRAG_WINDOW)
+        {
+            self.0.pop_front();
+        }
+    }
+
+    /// cursor velocity across the samples, in physics units per second
+    fn velocity(&self, converter: &CoordConverter) -> Vector<Real> {
+        match (self.0.front(), self.0.back()) {
+            (Some(&(t0, p0)), Some(&(t1, p1))) if t1 - t0 > f64::EPSILON => {
+                (converter.to_physics_point(p1) - converter.to_physics_point(p0))
+                    / (t1 - t0) as Real
+            }
+            _ => Vector::zeros(),
+        }
+    }
+}
+
+// desktop units the cursor can move in a frame and still be held still
+const STILL_DISTANCE: Real = 2.;
+
+/// Seconds the cursor has been held still while dragging, which charges the throw. Moving drains
+/// the charge as fast as holding still builds it, so the flick after a hold keeps most of it.
+#[derive(Component, Default)]
+pub(crate) struct DragCharge(f32);
+
+impl DragCharge {
+    /// how charged the throw is, from 0 to 1
+    pub(crate) fn fraction(&self, fling_config: &FlingConfig) -> f32 {
+        if fling_config.charge_time <= 0. {
+            0.
+        } else {
+            (self.0 / fling_config.charge_time).min(1.)
+        }
+    }
+}
+
+pub(crate) fn record_drag_history(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    mut window: Query<(
+        &PhysicsWindow,
+        &WindowState,
+        &mut DragHistory,
+        &mut DragCharge,
+    )>,
+    converter: Res<CoordConverter>,
+    fling_config: Res<FlingConfig>,
+) {
+    for (physics_window, window_state, mut history, mut charge) in window.iter_mut() {
+        if let WindowState::Dragging(_) = window_state {
+            if let Some((p, _)) =
+                cursor_on_desktop(&windows, &winit_windows, physics_window.id, &converter)
+            {
+                let still = history.0.back().map_or(false, |&(_, last)| {
+                    ((p.x - last.x).powi(2) + (p.y - last.y).powi(2)).sqrt() <= STILL_DISTANCE
+                });
+                let dt = time.delta_seconds();
+                charge.0 = if still {
+                    (charge.0 + dt).min(fling_config.charge_time)
+                } else {
+                    (charge.0 - dt).max(0.)
+                };
+                history.push(time.seconds_since_startup(), p);
+            }
+        }
+    }
+}
+
+// keeps the grabbed point of the window under the cursor, the body follows the OS window like it
+// does when static
+pub(crate) fn dragged_window_follows_cursor(
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    window_query: Query<(&PhysicsWindow, &WindowState)>,
+    converter: Res<CoordConverter>,
+) {
+    for (physics_window, window_state) in window_query.iter() {
+        let grab = match window_state {
+            WindowState::Dragging(grab) => *grab,
+            _ => continue,
+        };
+        let (cursor, _) =
+            match cursor_on_desktop(&windows, &winit_windows, physics_window.id, &converter) {
+                Some(cursor) => cursor,
+                None => continue,
+            };
+        let window = match winit_windows.get_window(physics_window.id) {
+            Some(window) => window,
+            None => continue,
+        };
+        let (inner, outer) = match (window.inner_position(), window.outer_position()) {
+            (Ok(inner), Ok(outer)) => (inner, outer),
+            _ => continue,
+        };
+
+        let top_left =
+            converter.to_physical(LogicalPosition::new(cursor.x - grab.x, cursor.y - grab.y));
+        // set_outer_position includes the title bar and borders
+        window.set_outer_position(PhysicalPosition::new(
+            top_left.x + outer.x - inner.x,
+            top_left.y + outer.y - inner.y,
+        ));
+    }
+}
+
+/// How far, in desktop units, the cursor has to move with the button held before the window is
+/// picked up. Shorter clicks, like clicking to focus the window, leave it alone.
+pub struct DragThreshold(pub Real);
+
+impl Default for DragThreshold {
+    fn default() -> Self {
+        Self(5.0)
+    }
+}
+
+/// A rectangle of the content area where pressing the left button never picks the window up, e.g.
+/// for a title bar drawn by the app. `origin` is its top left and `size` its width and height, in
+/// the window's logical pixels from the top left of the content area. Empty by default.
+#[derive(Default)]
+pub struct DragDeadZone {
+    pub origin: [f32; 2],
+    pub size: [f32; 2],
+}
+
+impl DragDeadZone {
+    // the cursor as bevy reports it, from the bottom left of the content area
+    fn contains(&self, cursor: Vec2, window_height: f32) -> bool {
+        let (x, y) = (
+            cursor.x - self.origin[0],
+            window_height - cursor.y - self.origin[1],
+        );
+        (0. ..self.size[0]).contains(&x) && (0. ..self.size[1]).contains(&y)
+    }
+}
+
+/// Where the left button went down, on the desktop and relative to the content area, until it is
+/// released.
+#[derive(Component, Default)]
+pub(crate) struct DragPress(Option<(LogicalPosition<Real>, LogicalPosition<Real>)>);
+
+// the press belongs to whichever window the cursor is over
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn pressing_grabs_window(
+    time: Res<Time>,
+    mouse_button: Res<Input<MouseButton>>,
+    mut window: Query<(
+        &PhysicsWindow,
+        &mut WindowState,
+        &mut DragHistory,
+        &mut DragCharge,
+        &mut DragPress,
+    )>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+    drag_threshold: Res<DragThreshold>,
+    dead_zone: Res<DragDeadZone>,
+) {
+    for (physics_window, mut window_state, mut history, mut charge, mut press) in window.iter_mut()
+    {
+        if mouse_button.just_released(MouseButton::Left) {
+            press.0 = None;
+            continue;
+        }
+
+        let cursor = cursor_on_desktop(&windows, &winit_windows, physics_window.id, &converter);
+        if mouse_button.just_pressed(MouseButton::Left) {
+            let in_dead_zone = windows.get(physics_window.id).map_or(false, |window| {
+                window
+                    .cursor_position()
+                    .map_or(false, |p| dead_zone.contains(p, window.height()))
+            });
+            press.0 = if in_dead_zone { None } else { cursor };
+        }
+
+        let (start, grab) = match press.0 {
+            Some(press) if !matches!(*window_state, WindowState::Dragging(_)) => press,
+            _ => continue,
+        };
+        if let Some((p, _)) = cursor {
+            let moved = ((p.x - start.x).powi(2) + (p.y - start.y).powi(2)).sqrt();
+            if moved > drag_threshold.0 {
+                // the point that was pressed is put back under the cursor
+                *window_state = WindowState::Dragging(grab);
+                history.0.clear();
+                history.push(time.seconds_since_startup(), p);
+                *charge = DragCharge::default();
+            }
+        }
+    }
+}
+
+/// How the cursor's velocity at release becomes an impulse on the window, and how much the shapes
+/// inside start spinning from it.
+///
+/// Holding the cursor still while dragging charges the throw: the impulse is multiplied by up to
+/// `max_charge` after `charge_time` seconds, growing linearly. An uncharged flick is thrown as
+/// usual.
+pub struct FlingConfig {
+    pub multiplier: Real,  // impulse per unit of cursor velocity
+    pub max_impulse: Real, // physics units, before charging
+    // 0 leaves the shapes to pick up spin from the walls alone, 1 spins them as if they were fixed
+    // to the window while it lurched away
+    pub spin: Real,
+    pub max_charge: Real,
+    pub charge_time: f32,
+}
+
+impl Default for FlingConfig {
+    fn default() -> Self {
+        Self {
+            multiplier: 0.5,
+            max_impulse: 1.0,
+            spin: 0.5,
+            max_charge: 3.0,
+            charge_time: 1.0,
+        }
+    }
+}
+
+impl FlingConfig {
+    fn impulse(&self, velocity: Vector<Real>, charge: &DragCharge) -> Vector<Real> {
+        let impulse = velocity * self.multiplier;
+        let magnitude = impulse.norm();
+        let impulse = if magnitude <= Real::EPSILON {
+            // the cursor was at rest when released
+            Vector::zeros()
+        } else if magnitude > self.max_impulse {
+            impulse * (self.max_impulse / magnitude)
+        } else {
+            impulse
+        };
+        impulse * (1. + (self.max_charge - 1.) * charge.fraction(self))
+    }
+}
+
+// physics units
+const SPIN_MIN_RADIUS: Real = 0.05;
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn dragging_flings_window(
+    time: Res<Time>,
+    mouse_button: Res<Input<MouseButton>>,
+    mut window: Query<(
+        Entity,
+        &PhysicsWindow,
+        &mut WindowState,
+        &mut DragHistory,
+        &DragCharge,
+        &RigidBodyPositionComponent,
+        &mut RigidBodyVelocityComponent,
+        &RigidBodyMassPropsComponent,
+    )>,
+    mut shapes: Query<
+        (
+            &InteriorShape,
+            &RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+        ),
+        Without<WindowState>,
+    >,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+    fling_config: Res<FlingConfig>,
+    time_scale: Res<TimeScale>,
+) {
+    if !mouse_button.just_released(MouseButton::Left) {
+        return;
+    }
+    for (
+        entity,
+        physics_window,
+        mut window_state,
+        mut history,
+        charge,
+        window_position,
+        mut window_velocity,
+        rbmp,
+    ) in window.iter_mut()
+    {
+        if let WindowState::Dragging(_) = *window_state {
+            *window_state = WindowState::Bouncing;
+            if let Some((curr, _)) =
+                cursor_on_desktop(&windows, &winit_windows, physics_window.id, &converter)
+            {
+                history.push(time.seconds_since_startup(), curr);
+            } else {
+                debug!("Failed to get cursor for drag end")
+            }
+            let release = match history.0.back() {
+                Some(&(_, p)) => p,
+                None => continue,
+            };
+            fling_window(
+                (entity, window_position, &mut *window_velocity, rbmp),
+                // so the window leaves the cursor at the cursor's on-screen speed
+                fling_config.impulse(history.velocity(&converter) / time_scale.0, charge),
+                converter.to_physics_point(release),
+                fling_config.spin,
+                shapes.iter_mut(),
+            );
+        }
+    }
+}
+
+// applies `impulse` to the window at `point`. Seen from the window, its shapes are thrown the
+// opposite way, and turn about its center as they go
+pub(crate) fn fling_window<'a>(
+    (window, window_position, window_velocity, rbmp): (
+        Entity,
+        &RigidBodyPositionComponent,
+        &mut RigidBodyVelocityComponent,
+        &RigidBodyMassPropsComponent,
+    ),
+    impulse: Vector<Real>,
+    point: Point<Real>,
+    spin: Real,
+    shapes: impl Iterator<
+        Item = (
+            &'a InteriorShape,
+            &'a RigidBodyPositionComponent,
+            Mut<'a, RigidBodyVelocityComponent>,
+        ),
+    >,
+) {
+    let before = window_velocity.linvel;
+    window_velocity.apply_impulse_at_point(rbmp, impulse, point);
+
+    let lurch = before - window_velocity.linvel;
+    let center = window_position.position.translation.vector;
+    for (shape, position, mut velocity) in shapes {
+        if shape.window != window {
+            continue;
+        }
+        let r = position.position.translation.vector - center;
+        // shapes close to the center would spin wildly otherwise
+        let r_squared = r.norm_squared().max(SPIN_MIN_RADIUS * SPIN_MIN_RADIUS);
+        velocity.angvel += spin * r.perp(&lurch) / r_squared;
+    }
+}
+
+/// Flings the primary window towards a random monitor corner with X, at the strongest uncharged
+/// throw, and every `auto_every` seconds if that is set, e.g. for an unattended display. Off by
+/// default.
+#[derive(Default)]
+pub struct DemoFling {
+    pub auto_every: Option<f64>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn x_flings_window_to_corner(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    demo: Res<DemoFling>,
+    mut last_fling: Local<f64>,
+    converter: Res<CoordConverter>,
+    fling_config: Res<FlingConfig>,
+    mut window: Query<
+        (
+            Entity,
+            &mut WindowState,
+            &RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+            &RigidBodyMassPropsComponent,
+        ),
+        With<PrimaryWindow>,
+    >,
+    mut shapes: Query<
+        (
+            &InteriorShape,
+            &RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+        ),
+        Without<WindowState>,
+    >,
+) {
+    use rand::seq::SliceRandom;
+
+    let now = time.seconds_since_startup();
+    let due = demo
+        .auto_every
+        .map_or(false, |every| now - *last_fling >= every);
+    if !keys.just_pressed(KeyCode::X) && !due {
+        return;
+    }
+    *last_fling = now;
+
+    let (entity, mut window_state, position, mut velocity, rbmp) = match window.get_single_mut() {
+        Ok(window) => window,
+        Err(e) => {
+            debug!("No primary window to fling: {}", e);
+            return;
+        }
+    };
+    let monitor = match converter.monitors.choose(&mut rand::thread_rng()) {
+        Some(monitor) => monitor,
+        None => return,
+    };
+    let [min, max] = converter.monitor_physics_bounds(monitor);
+    let corners = [min, max, Point::new(min.x, max.y), Point::new(max.x, min.y)];
+    let corner = corners
+        .choose(&mut rand::thread_rng())
+        .expect("corners is not empty");
+
+    let center = Point::from(position.position.translation.vector);
+    let direction = (corner - center)
+        .try_normalize(Real::EPSILON)
+        .unwrap_or_else(Vector::zeros);
+    *window_state = WindowState::Bouncing;
+    fling_window(
+        (entity, position, &mut *velocity, rbmp),
+        direction * fling_config.max_impulse,
+        center,
+        fling_config.spin,
+        shapes.iter_mut(),
+    );
+}
+
+/// Slows a fast window down while the cursor is over it near its center, so it can be caught.
+/// Off by default.
+pub struct AssistConfig {
+    pub enabled: bool,
+    pub radius: Real,    // physics units from the window's center
+    pub min_speed: Real, // physics units per second, slower windows are easy enough to catch
+    pub damping: Real,   // fraction of the speed lost per second, roughly
+}
+
+impl Default for AssistConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: 0.15,
+            min_speed: 0.3,
+            damping: 5.,
+        }
+    }
+}
+
+// winit only reports the cursor while it is over the window, so the radius is best kept within it
+pub(crate) fn cursor_near_window_slows_it(
+    time: Res<Time>,
+    assist: Res<AssistConfig>,
+    mut window: Query<(
+        &PhysicsWindow,
+        &WindowState,
+        &RigidBodyPositionComponent,
+        &mut RigidBodyVelocityComponent,
+    )>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+) {
+    if !assist.enabled {
+        return;
+    }
+    for (physics_window, window_state, position, mut velocity) in window.iter_mut() {
+        if !matches!(window_state, WindowState::Bouncing)
+            || velocity.linvel.norm() <= assist.min_speed
+        {
+            continue;
+        }
+        let cursor =
+            match cursor_to_physics(&windows, &winit_windows, physics_window.id, &converter) {
+                Some(cursor) => cursor,
+                None => continue,
+            };
+        let center = Point::from(position.position.translation.vector);
+        if (cursor - center).norm() <= assist.radius {
+            velocity.linvel *= (-assist.damping * time.delta_seconds()).exp();
+        }
+    }
+}
+
+/// Dotted path the window would take if let go now, drawn while it's dragged, like aiming a
+/// slingshot. It follows gravity alone, bounces and drag aren't predicted. A `seconds` of zero
+/// turns it off.
+pub struct FlingPreview {
+    pub seconds: Real, // of flight shown
+}
+
+impl Default for FlingPreview {
+    fn default() -> Self {
+        Self { seconds: 1. }
+    }
+}
+
+/// Path of the fling preview of `window`, in world coordinates.
+#[derive(Component)]
+pub(crate) struct FlingPreviewPath {
+    window: Entity,
+}
+
+// seconds of flight between the start of one dash and the next
+const PREVIEW_DASH_PERIOD: Real = 0.04;
+
+// the launch velocity is worked out like the release does it, from the cursor samples and the
+// charge, so the preview grows while the throw is charged
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn dragged_window_previews_fling(
+    mut commands: Commands,
+    preview: Res<FlingPreview>,
+    fling_config: Res<FlingConfig>,
+    time_scale: Res<TimeScale>,
+    rapier_config: Res<RapierConfiguration>,
+    converter: Res<CoordConverter>,
+    window: Query<(
+        Entity,
+        &WindowState,
+        &DragHistory,
+        &DragCharge,
+        &RigidBodyPositionComponent,
+        &RigidBodyVelocityComponent,
+        &RigidBodyMassPropsComponent,
+    )>,
+    mut paths: Query<(Entity, &FlingPreviewPath, &mut Path)>,
+) {
+    for (entity, preview_path, mut path) in paths.iter_mut() {
+        match window.get(preview_path.window) {
+            Ok((_, WindowState::Dragging(_), ..)) if preview.seconds > 0. => {}
+            Ok(_) => *path = PathBuilder::new().build(),
+            Err(_) => commands.entity(entity).despawn(),
+        }
+    }
+
+    if preview.seconds <= 0. {
+        return;
+    }
+    let [min, max] = converter.desktop_physics_bounds();
+    for (entity, window_state, history, charge, position, velocity, rbmp) in window.iter() {
+        if !matches!(window_state, WindowState::Dragging(_)) {
+            continue;
+        }
+        let impulse = fling_config.impulse(history.velocity(&converter) / time_scale.0, charge);
+        let launch = velocity.linvel + impulse * rbmp.local_mprops.inv_mass;
+        let start = Point::from(position.position.translation.vector);
+        let at = |t: Real| start + launch * t + rapier_config.gravity * (t * t / 2.);
+
+        let mut builder = PathBuilder::new();
+        let dashes = (preview.seconds / PREVIEW_DASH_PERIOD) as usize;
+        for i in 0..dashes {
+            let t = i as Real * PREVIEW_DASH_PERIOD;
+            let [from, to] = [at(t), at(t + PREVIEW_DASH_PERIOD / 2.)];
+            // off the desktop the window would have bounced already
+            if !(min.x..=max.x).contains(&from.x) || !(min.y..=max.y).contains(&from.y) {
+                break;
+            }
+            builder.move_to(Vec2::new(from.x, from.y) * converter.physics_scale);
+            builder.line_to(Vec2::new(to.x, to.y) * converter.physics_scale);
+        }
+
+        match paths.iter_mut().find(|(_, p, _)| p.window == entity) {
+            Some((_, _, mut path)) => *path = builder.build(),
+            None => {
+                commands
+                    .spawn_bundle(GeometryBuilder::build_as(
+                        &builder.build().0,
+                        DrawMode::Stroke(StrokeMode::new(Color::rgba(1., 1., 1., 0.6), 2.)),
+                        // in front of the shapes, behind the debug outlines
+                        Transform::from_xyz(0., 0., 5.),
+                    ))
+                    .insert(FlingPreviewPath { window: entity });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vector<Real>, b: Vector<Real>) {
+        assert!((a - b).norm() < 1e-5, "{:?} isn't {:?}", a, b);
+    }
+
+    #[test]
+    fn fling_impulse_is_clamped_to_max_impulse() {
+        let config = FlingConfig {
+            multiplier: 1.,
+            max_impulse: 1.,
+            ..Default::default()
+        };
+        let uncharged = DragCharge::default();
+        let impulse = |x, y| config.impulse(Vector::new(x, y), &uncharged);
+
+        // below and at the cap it passes through
+        assert_close(impulse(0.3, 0.4), Vector::new(0.3, 0.4));
+        assert_close(impulse(0.6, 0.8), Vector::new(0.6, 0.8));
+        // above it keeps its direction, however huge
+        assert_close(impulse(6., 8.), Vector::new(0.6, 0.8));
+        assert_close(impulse(3e6, -4e6), Vector::new(0.6, -0.8));
+        // a cursor at rest throws nothing rather than NaN
+        assert_eq!(impulse(0., 0.), Vector::zeros());
+    }
+}
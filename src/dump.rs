@@ -0,0 +1,134 @@
+use std::path::PathBuf;
+
+use bevy::{prelude::*, tasks::IoTaskPool};
+use bevy_rapier2d::prelude::*;
+use serde::Serialize;
+
+use crate::{shapes::InteriorShape, WindowState};
+
+/// Where F2 writes a JSON snapshot of the physics world, for attaching to bug reports about odd
+/// collisions. Relative paths are from the working directory. It's only ever written, there's no
+/// loading it back.
+pub struct PhysicsDump {
+    pub path: PathBuf,
+}
+
+impl Default for PhysicsDump {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("physics-dump.json"),
+        }
+    }
+}
+
+// everything in physics units
+#[derive(Serialize)]
+struct BodyDump {
+    entity: u32,
+    role: &'static str,
+    body_type: String,
+    position: [Real; 2],
+    angle: Real,
+    linvel: [Real; 2],
+    angvel: Real,
+}
+
+#[derive(Serialize)]
+struct ColliderDump {
+    entity: u32,
+    body: Option<u32>, // None for the monitor bounds, which have no body
+    shape: String,
+    half_extents: Option<[Real; 2]>,
+    radius: Option<Real>,
+    position: [Real; 2],
+    angle: Real,
+    memberships: u32,
+    filter: u32,
+}
+
+#[derive(Serialize)]
+struct WorldDump {
+    seconds_since_startup: f64,
+    bodies: Vec<BodyDump>,
+    colliders: Vec<ColliderDump>,
+}
+
+// the snapshot is taken in the frame F2 is pressed, and written on the IO task pool so a slow disk
+// doesn't hold up the frame
+pub(crate) fn f2_dumps_physics_world(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    dump: Res<PhysicsDump>,
+    io_pool: Res<IoTaskPool>,
+    bodies: Query<(
+        Entity,
+        &RigidBodyTypeComponent,
+        &RigidBodyPositionComponent,
+        &RigidBodyVelocityComponent,
+        Option<&WindowState>,
+        Option<&InteriorShape>,
+    )>,
+    colliders: Query<(
+        Entity,
+        &ColliderShapeComponent,
+        &ColliderPositionComponent,
+        &ColliderFlagsComponent,
+        Option<&ColliderParentComponent>,
+    )>,
+) {
+    if !keys.just_pressed(KeyCode::F2) {
+        return;
+    }
+    let world = WorldDump {
+        seconds_since_startup: time.seconds_since_startup(),
+        bodies: bodies
+            .iter()
+            .map(
+                |(entity, body_type, position, velocity, window, shape)| BodyDump {
+                    entity: entity.id(),
+                    role: match (window, shape) {
+                        (Some(_), _) => "window",
+                        (_, Some(_)) => "shape",
+                        _ => "other",
+                    },
+                    body_type: format!("{:?}", body_type.0),
+                    position: position.position.translation.vector.into(),
+                    angle: position.position.rotation.angle(),
+                    linvel: velocity.linvel.into(),
+                    angvel: velocity.angvel,
+                },
+            )
+            .collect(),
+        colliders: colliders
+            .iter()
+            .map(|(entity, shape, position, flags, parent)| ColliderDump {
+                entity: entity.id(),
+                body: parent.map(|parent| parent.handle.entity().id()),
+                shape: format!("{:?}", shape.shape_type()),
+                half_extents: shape.as_cuboid().map(|c| c.half_extents.into()),
+                radius: shape.as_ball().map(|b| b.radius),
+                position: position.translation.vector.into(),
+                angle: position.rotation.angle(),
+                memberships: flags.collision_groups.memberships,
+                filter: flags.collision_groups.filter,
+            })
+            .collect(),
+    };
+
+    let contents = match serde_json::to_string_pretty(&world) {
+        Ok(contents) => contents,
+        Err(e) => {
+            warn!("Failed to dump the physics world: {}", e);
+            return;
+        }
+    };
+    let path = dump.path.clone();
+    io_pool
+        .spawn(async move {
+            match std::fs::write(&path, contents) {
+                Ok(()) => info!("Dumped the physics world to {}", path.display()),
+                Err(e) => warn!("Failed to dump the physics world: {}", e),
+            }
+        })
+        .detach();
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{PrimaryWindow, WindowState};
+
+/// How hard the left stick pushes a bouncing window.
+pub struct GamepadConfig {
+    pub force: Real, // at full tilt, physics units
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self { force: 2.0 }
+    }
+}
+
+// the first gamepad to connect controls the primary window until it disconnects
+#[derive(Default)]
+pub(crate) struct ActiveGamepad(Option<Gamepad>);
+
+pub(crate) fn track_gamepad(
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut active: ResMut<ActiveGamepad>,
+) {
+    for GamepadEvent(gamepad, event_type) in gamepad_events.iter() {
+        match event_type {
+            GamepadEventType::Connected if active.0.is_none() => {
+                info!("{:?} controls the window", gamepad);
+                active.0 = Some(*gamepad);
+            }
+            GamepadEventType::Disconnected if active.0 == Some(*gamepad) => {
+                info!("{:?} disconnected", gamepad);
+                active.0 = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn gamepad_pushes_window(
+    active: Res<ActiveGamepad>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepad_config: Res<GamepadConfig>,
+    mut window: Query<
+        (
+            &WindowState,
+            &mut RigidBodyForcesComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        With<PrimaryWindow>,
+    >,
+) {
+    let (window_state, mut forces, mut activation) = match window.get_single_mut() {
+        Ok(window) => window,
+        Err(e) => {
+            debug!("No primary window to push: {}", e);
+            return;
+        }
+    };
+    let stick = match (active.0, window_state) {
+        (Some(gamepad), WindowState::Bouncing) => {
+            let axis = |axis_type| axes.get(GamepadAxis(gamepad, axis_type)).unwrap_or(0.);
+            Vector::new(
+                axis(GamepadAxisType::LeftStickX),
+                axis(GamepadAxisType::LeftStickY),
+            )
+        }
+        _ => Vector::zeros(),
+    };
+    forces.force += stick * gamepad_config.force;
+    if stick != Vector::zeros() {
+        activation.wake_up(true);
+    }
+}
+
+pub(crate) fn gamepad_toggles_physics(
+    active: Res<ActiveGamepad>,
+    buttons: Res<Input<GamepadButton>>,
+    mut window: Query<&mut WindowState, With<PrimaryWindow>>,
+) {
+    if let Some(gamepad) = active.0 {
+        if buttons.just_pressed(GamepadButton(gamepad, GamepadButtonType::South)) {
+            match window.get_single_mut() {
+                Ok(mut window_state) => window_state.toggle_physics(),
+                Err(e) => debug!("No primary window to toggle: {}", e),
+            }
+        }
+    }
+}
+
+pub(crate) fn gamepad_brakes_window(
+    active: Res<ActiveGamepad>,
+    buttons: Res<Input<GamepadButton>>,
+    mut window: Query<&mut RigidBodyVelocityComponent, With<PrimaryWindow>>,
+) {
+    if let Some(gamepad) = active.0 {
+        if buttons.pressed(GamepadButton(gamepad, GamepadButtonType::RightTrigger2)) {
+            let mut velocity = match window.get_single_mut() {
+                Ok(velocity) => velocity,
+                Err(e) => {
+                    debug!("No primary window to brake: {}", e);
+                    return;
+                }
+            };
+            velocity.linvel = Vector::zeros();
+            velocity.angvel = 0.;
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! The collision groups every collider is put in. Rapier lets two colliders touch only when each
+//! is a member of a group in the other's filter.
+//!
+//! Each window gets an interior group of its own, shared by its walls and its shapes, so shapes
+//! only ever meet the walls and shapes of their own window. Monitor bounds are members of every
+//! group, but their filter only takes in window bodies, and nothing while the windows wrap, and
+//! roaming shapes. Shapes are moved to the roaming group while [`ShapesRoam`] is on, leaving their
+//! window's walls behind for the monitor edges.
+//!
+//! | touches          | window bodies | monitor bounds | walls of window i | shapes of window i | roaming shapes |
+//! |------------------|---------------|----------------|-------------------|--------------------|----------------|
+//! | window bodies    | yes           | when bouncing  | no                | no                 | no             |
+//! | monitor bounds   | when bouncing | never (fixed)  | no                | no                 | yes            |
+//! | walls of i       | no            | no             | never (one body)  | yes                | no             |
+//! | shapes of i      | no            | no             | yes               | yes                | no             |
+//! | roaming shapes   | no            | yes            | no                | no                 | yes            |
+//!
+//! Walls and shapes of different windows never touch.
+//!
+//! [`ShapesRoam`]: crate::ShapesRoam
+
+use bevy_rapier2d::prelude::*;
+
+/// The window bodies, which bounce off each other and the monitor bounds.
+pub(crate) const WINDOW_BODY: u32 = 1;
+
+/// The monitor bounds' membership, every group.
+pub(crate) const MONITOR_BOUNDS: u32 = u32::MAX;
+
+/// Shapes let out of their windows, whichever window they came from.
+pub(crate) const ROAMING_SHAPES: u32 = 1 << 31;
+
+/// Windows get the groups between WINDOW_BODY and ROAMING_SHAPES, one each.
+pub(crate) const MAX_WINDOWS: usize = 30;
+
+/// The interior group of the window at `index`, 0 being the primary window.
+pub(crate) fn interior(index: usize) -> u32 {
+    1 << (index + 1)
+}
+
+pub(crate) fn window_body() -> InteractionGroups {
+    InteractionGroups::new(WINDOW_BODY, WINDOW_BODY)
+}
+
+pub(crate) fn window_walls(interior: u32) -> InteractionGroups {
+    InteractionGroups::new(interior, interior)
+}
+
+pub(crate) fn interior_shapes(interior: u32) -> InteractionGroups {
+    InteractionGroups::new(interior, interior)
+}
+
+pub(crate) fn roaming_shapes() -> InteractionGroups {
+    InteractionGroups::new(ROAMING_SHAPES, ROAMING_SHAPES)
+}
+
+/// The monitor bounds stop window bodies only when `stop_windows`, letting them through to wrap.
+/// Roaming shapes are always stopped.
+pub(crate) fn monitor_bounds(stop_windows: bool) -> InteractionGroups {
+    let windows = if stop_windows { WINDOW_BODY } else { 0 };
+    InteractionGroups::new(MONITOR_BOUNDS, windows | ROAMING_SHAPES)
+}
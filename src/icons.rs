@@ -0,0 +1,74 @@
+use std::mem::{discriminant, Discriminant};
+
+use bevy::{prelude::*, utils::HashMap, winit::WinitWindows};
+use winit::window::Icon;
+
+use crate::{PhysicsWindow, WindowState};
+
+// pixels across an icon
+const ICON_SIZE: u32 = 32;
+
+// a dot per window state, in the window backgrounds' colours, built once so a change of state only
+// swaps which one is shown
+pub(crate) struct StateIcons {
+    bouncing: Option<Icon>,
+    dragging: Option<Icon>,
+    static_: Option<Icon>,
+}
+
+impl StateIcons {
+    fn for_state(&self, state: &WindowState) -> Option<&Icon> {
+        match state {
+            WindowState::Bouncing => self.bouncing.as_ref(),
+            WindowState::Dragging(_) => self.dragging.as_ref(),
+            WindowState::Static => self.static_.as_ref(),
+        }
+    }
+}
+
+pub(crate) fn build_state_icons(mut commands: Commands) {
+    commands.insert_resource(StateIcons {
+        bouncing: dot_icon(Color::NAVY),
+        dragging: dot_icon(Color::ORANGE),
+        static_: dot_icon(Color::GRAY),
+    });
+}
+
+// a filled circle on a transparent square, its edge softened over a pixel
+fn dot_icon(colour: Color) -> Option<Icon> {
+    let radius = ICON_SIZE as f32 / 2.;
+    let [r, g, b, _] = colour.as_rgba_f32().map(|c| (c * 255.) as u8);
+    let rgba = (0..ICON_SIZE * ICON_SIZE)
+        .flat_map(|i| {
+            let x = (i % ICON_SIZE) as f32 + 0.5 - radius;
+            let y = (i / ICON_SIZE) as f32 + 0.5 - radius;
+            let coverage = (radius - (x * x + y * y).sqrt()).clamp(0., 1.);
+            [r, g, b, (coverage * 255.) as u8]
+        })
+        .collect();
+    match Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE) {
+        Ok(icon) => Some(icon),
+        Err(e) => {
+            warn!("Failed to build a window icon: {}", e);
+            None
+        }
+    }
+}
+
+// the icon is only set when the kind of state changes, not when a drag moves its grab point. Some
+// platforms, macOS and Wayland among them, have no window icons and ignore this
+pub(crate) fn window_icon_follows_state(
+    icons: Res<StateIcons>,
+    winit_windows: Res<WinitWindows>,
+    mut shown: Local<HashMap<Entity, Discriminant<WindowState>>>,
+    window_query: Query<(Entity, &PhysicsWindow, &WindowState), Changed<WindowState>>,
+) {
+    for (entity, physics_window, window_state) in window_query.iter() {
+        if shown.insert(entity, discriminant(window_state)) == Some(discriminant(window_state)) {
+            continue;
+        }
+        if let Some(window) = winit_windows.get_window(physics_window.id) {
+            window.set_window_icon(icons.for_state(window_state).cloned());
+        }
+    }
+}
@@ -0,0 +1,2242 @@
+//! Turns the primary window into a physics object: it can be thrown around the desktop, bounces
+//! off the monitor edges and carries shapes around inside it.
+//!
+//! Add [`WindowPhysicsPlugin`] after `DefaultPlugins`, `ShapePlugin` and Rapier's plugin. The
+//! common settings are set through its builder methods, and any of the config resources can be
+//! inserted beforehand to override their defaults.
+
+use bevy::{
+    diagnostic::Diagnostics,
+    ecs::schedule::ShouldRun,
+    prelude::*,
+    render::camera::ActiveCameras,
+    utils::HashMap,
+    window::{CreateWindow, WindowId, WindowResized},
+    winit::WinitWindows,
+};
+use bevy_rapier2d::{
+    physics::{PhysicsSystems, SimulationToRenderTime, TimestepMode},
+    prelude::*,
+};
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition};
+
+mod coords;
+mod debug_render;
+mod drag;
+mod dump;
+mod gamepad;
+mod groups;
+mod icons;
+mod magnetism;
+mod minimap;
+mod monitors;
+mod multi_window;
+mod overlay;
+mod persistence;
+mod replay;
+mod scene;
+mod screenshot;
+mod shapes;
+mod simulation;
+mod sound;
+mod stats;
+mod trails;
+mod wind;
+mod zoom;
+
+use coords::{box_collider, CoordConverter, MonitorRect};
+use debug_render::{draw_collider_outlines, f1_toggles_debug_render};
+use drag::{
+    cursor_near_window_slows_it, dragged_window_follows_cursor, dragged_window_previews_fling,
+    dragging_flings_window, pressing_grabs_window, record_drag_history, x_flings_window_to_corner,
+    DragCharge, DragHistory, DragPress,
+};
+use dump::f2_dumps_physics_world;
+use gamepad::{
+    gamepad_brakes_window, gamepad_pushes_window, gamepad_toggles_physics, track_gamepad,
+    ActiveGamepad,
+};
+use icons::{build_state_icons, window_icon_follows_state};
+use magnetism::{
+    edges_attract_window, forces_last_every_step, reset_forces, window_orbits_cursor, AddForces,
+    ResetForces,
+};
+use minimap::{f4_toggles_minimap, minimap_follows_window, minimap_shows_monitors, spawn_minimap};
+use monitors::{monitor_changes_refresh_bounds, spawn_monitor_bounds};
+use multi_window::{open_extra_windows, spawn_extra_window_bodies, MultiWindowRenderPlugin};
+use overlay::{f3_toggles_diagnostics, spawn_diagnostics_overlay, update_diagnostics};
+use persistence::{save_window_on_exit, SavedWindow};
+use replay::{function_keys_control_recorder, record_window, replay_window};
+use scene::{load_scene, save_scene};
+use screenshot::{f12_takes_screenshot, ScreenshotRenderPlugin, ScreenshotRequest};
+use shapes::{
+    apply_shape_roaming, apply_zero_g_interior, b_drops_bowling_ball, bounces_throw_sparks,
+    c_bursts_confetti, collisions_shatter_shapes, colour_shapes, digit_0_toggles_zero_g,
+    e_explodes_at_cursor, escaped_shapes_drain, expired_shapes_despawn, g_pulls_shapes_to_cursor,
+    index_new_shapes, m_toggles_wall_magnet, n_spawns_shape_at_cursor, right_click_deletes_shape,
+    shapes_cling_to_walls, shapes_fall_towards_cursor, shift_scroll_changes_shape_count,
+    spawn_shapes, sprites_replace_fills, tab_toggles_roaming_shapes, v_vacuums_shapes_to_center,
+    z_freezes_shapes, BowlingBall, InteriorShape, NextSpawnIndex, ShapeRng,
+};
+use simulation::{
+    apply_time_scale, period_steps_paused_physics, plus_minus_change_time_scale,
+    title_shows_status, toggle_pause_on_p, OweTime, StepControl, StepRequest, StepsThisFrame,
+};
+use sound::{load_bounce_sounds, window_hitting_monitor_plays_sound};
+use stats::{count_collisions, delete_resets_collision_stats};
+use trails::{draw_trails, keys_control_paint, shapes_paint_dots, Canvas};
+use wind::{ijkl_change_wind, show_wind, spawn_wind_indicator, wind_pushes_bodies};
+use zoom::{camera_matches_monitor_scale, home_resets_zoom, mouse_wheel_zooms_window, CameraZoom};
+
+pub use debug_render::DebugRender;
+pub use drag::{AssistConfig, DemoFling, DragDeadZone, DragThreshold, FlingConfig, FlingPreview};
+pub use dump::PhysicsDump;
+pub use gamepad::GamepadConfig;
+pub use magnetism::{EdgeMagnetism, OrbitConfig};
+pub use replay::Recorder;
+pub use screenshot::ScreenshotConfig;
+pub use shapes::{
+    BowlingConfig, ColorMode, ConfettiConfig, EscapeAction, EscapeConfig, ExplosionConfig,
+    FreezeConfig, JellyConfig, PlanetGravity, ShapeLimit, ShapeRender, ShapeWeights, ShapesRoam,
+    ShatterConfig, SparkConfig, SpawnConfig, SpawnSeed, TextureConfig, VacuumConfig, WallMagnet,
+    WellConfig, ZeroGInterior,
+};
+pub use simulation::{SimulationPaused, TimeScale, PHYSICS_DT};
+pub use sound::SoundConfig;
+pub use stats::CollisionStats;
+pub use trails::{PaintMode, TrailConfig};
+pub use wind::Wind;
+
+/// How many windows are thrown around. The first is the app's primary window and the plugin opens
+/// the rest. The windows bounce off each other, and each one's walls only hold its own shapes.
+pub struct WindowCount(pub usize);
+
+impl Default for WindowCount {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// Which OS window a body stands in for, and the collision group its walls and shapes share.
+#[derive(Component, Clone, Copy)]
+pub(crate) struct PhysicsWindow {
+    pub(crate) id: WindowId,
+    pub(crate) interior: u32,
+}
+
+/// Marks the body of the primary window, which is the one saved on exit, shown in the background
+/// colour and driven by the gamepad.
+#[derive(Component)]
+pub(crate) struct PrimaryWindow;
+
+/// What a window's body is doing. Attached to each entity standing in for an OS window.
+#[derive(Component, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum WindowState {
+    Bouncing,
+    /// held by the cursor at this point, measured from the top left of the content area in desktop
+    /// units
+    Dragging(LogicalPosition<Real>),
+    Static,
+}
+
+impl Default for WindowState {
+    fn default() -> Self {
+        Self::Static
+    }
+}
+
+impl WindowState {
+    fn toggle_physics(&mut self) {
+        *self = match *self {
+            WindowState::Static | WindowState::Dragging(_) => WindowState::Bouncing,
+            WindowState::Bouncing => WindowState::Static,
+        }
+    }
+}
+
+/// Sent when the [`WindowState`] of the body `window` changes, e.g. when it is grabbed, thrown or
+/// stopped.
+#[derive(Debug)]
+pub struct WindowStateChanged {
+    pub window: Entity,
+    pub from: WindowState,
+    pub to: WindowState,
+}
+
+// the state each window was last seen in, Changed only tells what it is now
+fn send_window_state_changes(
+    window_query: Query<(Entity, &WindowState), Changed<WindowState>>,
+    mut last_states: Local<HashMap<Entity, WindowState>>,
+    mut state_changes: EventWriter<WindowStateChanged>,
+) {
+    for (window, &to) in window_query.iter() {
+        // newly spawned windows only start being tracked
+        match last_states.insert(window, to) {
+            // a state can be written back unchanged
+            Some(from) if std::mem::discriminant(&from) != std::mem::discriminant(&to) => {
+                state_changes.send(WindowStateChanged { window, from, to })
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Sent when the body `window` starts touching a monitor edge, at `point` in physics units, moving
+/// at `speed` physics units per second.
+#[derive(Debug)]
+pub struct WindowBounced {
+    pub window: Entity,
+    pub point: Point<Real>,
+    pub speed: Real,
+}
+
+// the contact point comes from the narrow phase, which still holds the contact the frame after it
+// started
+fn send_window_bounces(
+    mut contact_events: EventReader<ContactEvent>,
+    narrow_phase: Res<NarrowPhase>,
+    window_query: Query<&RigidBodyVelocityComponent, With<WindowState>>,
+    monitors: Query<Entity, With<MonitorBounds>>,
+    mut bounces: EventWriter<WindowBounced>,
+) {
+    for event in contact_events.iter() {
+        let (h1, h2) = match event {
+            ContactEvent::Started(h1, h2) => (*h1, *h2),
+            ContactEvent::Stopped(_, _) => continue,
+        };
+        let (window, velocity) =
+            match (window_query.get(h1.entity()), window_query.get(h2.entity())) {
+                (Ok(velocity), _) if monitors.get(h2.entity()).is_ok() => (h1.entity(), velocity),
+                (_, Ok(velocity)) if monitors.get(h1.entity()).is_ok() => (h2.entity(), velocity),
+                _ => continue,
+            };
+        let point = narrow_phase
+            .contact_pair(h1, h2)
+            .and_then(|pair| {
+                pair.manifolds
+                    .iter()
+                    .find_map(|m| m.data.solver_contacts.first())
+            })
+            .map(|contact| contact.point);
+        if let Some(point) = point {
+            bounces.send(WindowBounced {
+                window,
+                point,
+                speed: velocity.linvel.norm(),
+            });
+        }
+    }
+}
+
+/// Where the OS window was last moved to while bouncing, in physical pixels.
+#[derive(Component, Default)]
+struct PlacedAt(Option<PhysicalPosition<i32>>);
+
+#[derive(Component)]
+pub(crate) struct WindowWalls;
+
+#[derive(Component)]
+struct MonitorBounds;
+
+/// What happens when the window reaches the edge of the desktop.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BoundaryMode {
+    Bounce,
+    /// leave one side and come back in on the opposite one
+    Wrap,
+}
+
+impl Default for BoundaryMode {
+    fn default() -> Self {
+        Self::Bounce
+    }
+}
+
+impl BoundaryMode {
+    // the monitor edges only stop windows when bouncing
+    fn monitor_groups(self) -> InteractionGroups {
+        groups::monitor_bounds(self == BoundaryMode::Bounce)
+    }
+}
+
+/// How much of a window, in desktop units across and down, is kept on some monitor however hard it
+/// is thrown, so it can always be grabbed again.
+pub struct OnScreenMargin(pub Real);
+
+impl Default for OnScreenMargin {
+    fn default() -> Self {
+        Self(50.)
+    }
+}
+
+/// Pixels per physics unit, handed to Rapier when the plugin is built.
+///
+/// Changing it at runtime only affects what is converted afterwards: existing colliders keep their
+/// size in physics units, so they no longer match the shapes drawn for them.
+///
+/// The pixels are desktop units, the logical pixels of the monitor the window started on. A
+/// window on a monitor with another scale factor has its camera scaled to match, so shapes stay
+/// the same size on screen without this changing.
+#[derive(Clone, Copy)]
+pub struct PhysicsScale(pub Real);
+
+impl Default for PhysicsScale {
+    fn default() -> Self {
+        Self(1500.)
+    }
+}
+
+/// Density of the window's body. Its mass is this times its outer area in physics units, so a
+/// heavier window travels less far for the same fling and pushes the shapes around harder.
+pub struct WindowDensity(pub Real);
+
+impl Default for WindowDensity {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Restitution of the window, its walls, the monitor edges and the shapes, between 0 and 1.
+///
+/// Restitutions are averaged on contact, so at 1 the window keeps bouncing between the monitor
+/// edges without losing speed.
+pub struct Bounciness(pub Real);
+
+impl Default for Bounciness {
+    fn default() -> Self {
+        Self(0.4)
+    }
+}
+
+impl Bounciness {
+    const STEP: Real = 0.1;
+}
+
+fn brackets_change_bounciness(keys: Res<Input<KeyCode>>, mut bounciness: ResMut<Bounciness>) {
+    let step = if keys.just_pressed(KeyCode::RBracket) {
+        Bounciness::STEP
+    } else if keys.just_pressed(KeyCode::LBracket) {
+        -Bounciness::STEP
+    } else {
+        return;
+    };
+    bounciness.0 = (bounciness.0 + step).clamp(0., 1.);
+}
+
+// new colliders, like shapes spawned with N, pick up the current value too
+fn apply_bounciness(
+    bounciness: Res<Bounciness>,
+    mut materials: Query<
+        &mut ColliderMaterialComponent,
+        Or<(
+            With<WindowState>,
+            With<WindowWalls>,
+            With<MonitorBounds>,
+            With<InteriorShape>,
+        )>,
+    >,
+) {
+    for mut material in materials.iter_mut() {
+        if bounciness.is_changed() || material.is_added() {
+            material.restitution = bounciness.0;
+        }
+    }
+}
+
+/// Each bounce off a monitor edge taking `falloff` of what's left of the window's restitution
+/// away, down to `min_fraction` of [`Bounciness`], so a throw settles gradually instead of
+/// bouncing evenly until it stops. The bounces are counted from when the window was last let go or
+/// set bouncing.
+/// A `falloff` of 0 keeps it at [`Bounciness`].
+pub struct RestitutionFalloff {
+    pub falloff: Real,
+    pub min_fraction: Real,
+}
+
+impl Default for RestitutionFalloff {
+    fn default() -> Self {
+        Self {
+            falloff: 0.1,
+            min_fraction: 0.3,
+        }
+    }
+}
+
+impl RestitutionFalloff {
+    fn restitution(&self, base: Real, bounces: u32) -> Real {
+        base * (1. - self.falloff)
+            .clamp(0., 1.)
+            .powi(bounces as i32)
+            .max(self.min_fraction)
+    }
+}
+
+/// Monitor edges the window has bounced off since it started bouncing.
+#[derive(Component, Default)]
+struct BounceCount(u32);
+
+// the restitution is set every frame rather than on a bounce, so it's back in place right after
+// apply_bounciness resets every collider
+fn bounces_wear_restitution(
+    bounciness: Res<Bounciness>,
+    falloff: Res<RestitutionFalloff>,
+    mut contact_events: EventReader<ContactEvent>,
+    monitors: Query<Entity, With<MonitorBounds>>,
+    mut window_query: Query<(
+        &WindowState,
+        &mut BounceCount,
+        &mut ColliderMaterialComponent,
+    )>,
+) {
+    for event in contact_events.iter() {
+        let (e1, e2) = match event {
+            ContactEvent::Started(h1, h2) => (h1.entity(), h2.entity()),
+            ContactEvent::Stopped(_, _) => continue,
+        };
+        let window = if monitors.get(e2).is_ok() {
+            e1
+        } else if monitors.get(e1).is_ok() {
+            e2
+        } else {
+            continue;
+        };
+        if let Ok((WindowState::Bouncing, mut bounces, _)) = window_query.get_mut(window) {
+            bounces.0 += 1;
+        }
+    }
+
+    for (window_state, mut bounces, mut material) in window_query.iter_mut() {
+        if !matches!(window_state, WindowState::Bouncing) && bounces.0 != 0 {
+            bounces.0 = 0;
+        }
+        let restitution = falloff.restitution(bounciness.0, bounces.0);
+        if material.restitution != restitution {
+            material.restitution = restitution;
+        }
+    }
+}
+
+/// Friction of the window, its walls and the monitor edges, 0 for windows sliding freely along the
+/// edges. Shapes keep their own.
+///
+/// Frictions are averaged on contact like restitutions, but act along the edge rather than across
+/// it. In a corner the window touches two edges at once, so a high [`Bounciness`] keeps pressing
+/// it into one edge while it bounces off the other, and high friction there turns that into
+/// jerky stops and hops out of the corner instead of settling. Lower one of the two to calm it.
+pub struct ContactFriction(pub Real);
+
+impl Default for ContactFriction {
+    fn default() -> Self {
+        Self(0.8)
+    }
+}
+
+fn apply_contact_friction(
+    friction: Res<ContactFriction>,
+    mut materials: Query<
+        &mut ColliderMaterialComponent,
+        Or<(With<WindowState>, With<WindowWalls>, With<MonitorBounds>)>,
+    >,
+) {
+    for mut material in materials.iter_mut() {
+        if friction.is_changed() || material.is_added() {
+            material.friction = friction.0;
+        }
+    }
+}
+
+/// Continuous collision detection, so a body moving further than its own size in one physics step
+/// still hits whatever it passes instead of tunnelling through it. On for the windows, which a hard
+/// fling could otherwise carry through a monitor edge. It costs solver time, so shapes only get it
+/// while faster than `shape_speed`, in physics units per second, and never when it is None.
+///
+/// With `dragged_shapes`, every shape of a window being dragged gets it too, however slow. The OS
+/// can move a dragged window further in a frame than its shapes are thick, and without it a fast
+/// drag sweeps the trailing wall past the shapes, leaving them outside.
+pub struct ContinuousCollision {
+    pub windows: bool,
+    pub shape_speed: Option<Real>,
+    pub dragged_shapes: bool,
+}
+
+impl Default for ContinuousCollision {
+    fn default() -> Self {
+        Self {
+            windows: true,
+            shape_speed: Some(2.),
+            dragged_shapes: true,
+        }
+    }
+}
+
+// rapier works out the thickness and distance CCD needs from the colliders, so only the flag is
+// set. It's only written when it changes, to keep rapier from updating the bodies needlessly.
+// A dragged window is kinematic, moved by setting its next position, and rapier's CCD sweeps the
+// shapes against the walls' motion from one position to the next
+fn apply_continuous_collision(
+    ccd: Res<ContinuousCollision>,
+    mut windows: Query<(Entity, &WindowState, &mut RigidBodyCcdComponent), Without<InteriorShape>>,
+    mut shapes: Query<
+        (
+            &InteriorShape,
+            &mut RigidBodyCcdComponent,
+            &RigidBodyVelocityComponent,
+            Option<&BowlingBall>,
+        ),
+        Without<WindowState>,
+    >,
+) {
+    let mut dragged = Vec::new();
+    for (entity, state, mut window) in windows.iter_mut() {
+        if window.ccd_enabled != ccd.windows {
+            window.ccd_enabled = ccd.windows;
+        }
+        if let WindowState::Dragging(_) = state {
+            dragged.push(entity);
+        }
+    }
+    for (interior, mut shape, velocity, bowling_ball) in shapes.iter_mut() {
+        // a bowling ball is heavy enough to shove the shapes it hits through the walls
+        let fast = bowling_ball.is_some()
+            || (ccd.dragged_shapes && dragged.contains(&interior.window))
+            || ccd
+                .shape_speed
+                .map_or(false, |speed| velocity.linvel.norm() > speed);
+        if shape.ccd_enabled != fast {
+            shape.ccd_enabled = fast;
+        }
+    }
+}
+
+/// When rapier lets resting windows and shapes sleep, skipping them until something hits them. A
+/// body falls asleep once its kinetic energy per unit of mass has stayed below `threshold` for a
+/// while. A higher threshold puts bodies to sleep sooner, which saves solver time with many shapes,
+/// and None keeps every body awake. How many are asleep is shown in the F3 diagnostics.
+///
+/// Changes to gravity or the wind wake every body either way, so none is left hanging in the air.
+pub struct SleepConfig {
+    pub threshold: Option<Real>,
+}
+
+impl Default for SleepConfig {
+    fn default() -> Self {
+        Self {
+            threshold: Some(RigidBodyActivation::default_threshold()),
+        }
+    }
+}
+
+// a negative threshold is rapier's way of never sleeping. It's only written when it changes, like
+// the CCD flag
+fn apply_sleep_config(
+    sleep: Res<SleepConfig>,
+    mut bodies: Query<
+        &mut RigidBodyActivationComponent,
+        Or<(With<WindowState>, With<InteriorShape>)>,
+    >,
+) {
+    let threshold = sleep.threshold.unwrap_or(-1.);
+    for mut activation in bodies.iter_mut() {
+        if activation.threshold != threshold {
+            activation.threshold = threshold;
+            if sleep.threshold.is_none() {
+                activation.wake_up(true);
+            }
+        }
+    }
+}
+
+/// Air drag slowing the bouncing windows down, toggled with F.
+pub struct AirDrag {
+    pub enabled: bool,
+    pub linear_damping: Real,
+    // physics units per second, a window slower than this for REST_TIME goes static. None keeps it
+    // bouncing
+    pub rest_speed: Option<Real>,
+}
+
+impl Default for AirDrag {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            linear_damping: 0.5,
+            rest_speed: None,
+        }
+    }
+}
+
+// long enough that a window toggled to bouncing, which starts out at rest, can get going
+const REST_TIME: f32 = 0.5;
+
+/// Seconds the window has been slower than [`AirDrag::rest_speed`].
+#[derive(Component, Default)]
+struct SlowFor(f32);
+
+fn f_toggles_air_drag(keys: Res<Input<KeyCode>>, mut air_drag: ResMut<AirDrag>) {
+    if keys.just_pressed(KeyCode::F) {
+        air_drag.enabled = !air_drag.enabled;
+    }
+}
+
+fn apply_air_drag(
+    air_drag: Res<AirDrag>,
+    mut window_query: Query<&mut RigidBodyDampingComponent, With<WindowState>>,
+) {
+    for mut damping in window_query.iter_mut() {
+        if air_drag.is_changed() || damping.is_added() {
+            damping.linear_damping = if air_drag.enabled {
+                air_drag.linear_damping
+            } else {
+                0.
+            };
+        }
+    }
+}
+
+// a static window isn't moved every frame, unlike one bouncing ever so slowly
+fn resting_window_goes_static(
+    time: Res<Time>,
+    air_drag: Res<AirDrag>,
+    mut window_query: Query<(&mut WindowState, &RigidBodyVelocityComponent, &mut SlowFor)>,
+) {
+    let rest_speed = match air_drag.rest_speed {
+        Some(rest_speed) => rest_speed,
+        None => return,
+    };
+    for (mut window_state, velocity, mut slow_for) in window_query.iter_mut() {
+        if !matches!(*window_state, WindowState::Bouncing) || velocity.linvel.norm() >= rest_speed {
+            slow_for.0 = 0.;
+            continue;
+        }
+        slow_for.0 += time.delta_seconds();
+        if slow_for.0 >= REST_TIME {
+            *window_state = WindowState::Static;
+            slow_for.0 = 0.;
+        }
+    }
+}
+
+/// Cell size of the grid, in desktop units, that a window's top-left corner snaps to when it stops
+/// bouncing. 0 leaves it where it stopped.
+#[derive(Default)]
+pub struct SnapGrid(pub Real);
+
+// the OS window is moved, the body follows it like any static window
+fn stopping_window_snaps_to_grid(
+    snap_grid: Res<SnapGrid>,
+    window_query: Query<(&PhysicsWindow, &WindowState, ChangeTrackers<WindowState>)>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+) {
+    if snap_grid.0 <= 0. {
+        return;
+    }
+    for (physics_window, window_state, tracker) in window_query.iter() {
+        // restored windows start out static and stay where they were left
+        if !matches!(window_state, WindowState::Static)
+            || !tracker.is_changed()
+            || tracker.is_added()
+        {
+            continue;
+        }
+        let window = match winit_windows.get_window(physics_window.id) {
+            Some(window) => window,
+            None => continue,
+        };
+        if let Ok(outer) = window.outer_position() {
+            let top_left = converter.from_physical(outer);
+            let snap = |v: Real| (v / snap_grid.0).round() * snap_grid.0;
+            window.set_outer_position(
+                converter.to_physical(LogicalPosition::new(snap(top_left.x), snap(top_left.y))),
+            );
+        }
+    }
+}
+
+/// Keeps the windows above other apps, toggled with O as A is one of the WASD keys moving the
+/// window. Shown in the title bar.
+#[derive(Default)]
+pub struct AlwaysOnTop(pub bool);
+
+fn o_toggles_always_on_top(keys: Res<Input<KeyCode>>, mut always_on_top: ResMut<AlwaysOnTop>) {
+    if keys.just_pressed(KeyCode::O) {
+        always_on_top.0 = !always_on_top.0;
+    }
+}
+
+fn apply_always_on_top(
+    always_on_top: Res<AlwaysOnTop>,
+    window_query: Query<&PhysicsWindow>,
+    winit_windows: Res<WinitWindows>,
+) {
+    if !always_on_top.is_changed() {
+        return;
+    }
+    for physics_window in window_query.iter() {
+        if let Some(window) = winit_windows.get_window(physics_window.id) {
+            window.set_always_on_top(always_on_top.0);
+        }
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if always_on_top.0 {
+        use winit::platform::unix::WindowExtUnix;
+        let on_wayland = winit_windows
+            .get_window(WindowId::primary())
+            .map_or(false, |window| window.wayland_surface().is_some());
+        if on_wayland {
+            warn!(
+                "Wayland doesn't let apps keep their windows on top, the windows stay as they are"
+            );
+        }
+    }
+}
+
+/// Whether the windows have their title bars and borders, toggled with U as D is one of the WASD
+/// keys moving the window. Without them the OS has nothing to move the windows by, so dragging
+/// inside them is the only way.
+pub struct Decorations(pub bool);
+
+impl Default for Decorations {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+fn u_toggles_decorations(keys: Res<Input<KeyCode>>, mut decorations: ResMut<Decorations>) {
+    if keys.just_pressed(KeyCode::U) {
+        decorations.0 = !decorations.0;
+    }
+}
+
+fn apply_decorations(
+    decorations: Res<Decorations>,
+    window_query: Query<&PhysicsWindow>,
+    winit_windows: Res<WinitWindows>,
+) {
+    if !decorations.is_changed() {
+        return;
+    }
+    for physics_window in window_query.iter() {
+        if let Some(window) = winit_windows.get_window(physics_window.id) {
+            window.set_decorations(decorations.0);
+        }
+    }
+}
+
+// the OS redraws the frame a while after decorations are toggled, without resizing the content, so
+// rather than waiting for a resize the body is measured against the OS window every frame. When
+// the outer size has changed, the body, the walls and the camera are fitted to it again
+fn frame_follows_decorations(
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+    mut window_query: Query<
+        (&PhysicsWindow, &Children, &mut ColliderShapeComponent),
+        (With<WindowState>, Without<WindowWalls>),
+    >,
+    mut walls_query: Query<
+        (&mut ColliderShapeComponent, &mut ColliderParentComponent),
+        (With<WindowWalls>, Without<WindowState>),
+    >,
+    mut cameras: Query<&mut Transform, With<Camera>>,
+) {
+    for (physics_window, children, mut shape) in window_query.iter_mut() {
+        let window = match winit_windows.get_window(physics_window.id) {
+            Some(window) => window,
+            None => continue,
+        };
+        let outer_size = converter.from_physical_size(window.outer_size());
+        let halfbounds = converter.to_physics_vec(outer_size) / 2.;
+        let unchanged = shape.as_cuboid().map_or(true, |cuboid| {
+            (cuboid.half_extents - halfbounds).norm() < 1e-4
+        });
+        if unchanged {
+            continue;
+        }
+        *shape = ColliderShape::cuboid(halfbounds[0], halfbounds[1]).into();
+
+        let offset = content_offset(window, &converter);
+        let inner_size = converter.from_physical_size(window.inner_size());
+        let inner_dims = converter.to_physics_vec(inner_size);
+        for &child in children.iter() {
+            if let Ok((mut walls, mut parent)) = walls_query.get_mut(child) {
+                *walls = box_collider((inner_dims / 2.).into()).into();
+                parent.pos_wrt_parent = Isometry::new(offset, 0.);
+            }
+            if let Ok(mut camera) = cameras.get_mut(child) {
+                camera.translation.x = offset.x * converter.physics_scale;
+                camera.translation.y = offset.y * converter.physics_scale;
+            }
+        }
+    }
+}
+
+/// Lets the window's body spin when flung off-center. Experimental.
+///
+/// OS windows can't rotate, so the rotation is physics-only: the OS window is placed from the
+/// body's center alone, as if it were upright, and the camera is turned back upright so the shapes
+/// are drawn where they are. The walls the shapes collide with do rotate, so they can be seen
+/// tumbling inside a box the window no longer matches.
+#[derive(Default)]
+pub struct RotationEnabled(pub bool);
+
+fn apply_rotation_enabled(
+    rotation_enabled: Res<RotationEnabled>,
+    mut window_query: Query<
+        (
+            &mut RigidBodyMassPropsComponent,
+            &mut RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+        ),
+        With<WindowState>,
+    >,
+) {
+    if !rotation_enabled.is_changed() {
+        return;
+    }
+    for (mut mass_props, mut position, mut velocity) in window_query.iter_mut() {
+        mass_props.flags.set(
+            RigidBodyMassPropsFlags::ROTATION_LOCKED,
+            !rotation_enabled.0,
+        );
+        if !rotation_enabled.0 {
+            velocity.angvel = 0.;
+            position.position.rotation = Rotation::identity();
+            position.next_position.rotation = Rotation::identity();
+        }
+        mass_props.update_world_mass_properties(&position.position);
+    }
+}
+
+// the camera is a child of the window's body, so undo the body's rotation
+fn camera_stays_upright(
+    window_query: Query<(&Transform, &Children), (With<WindowState>, Without<Camera>)>,
+    mut cameras: Query<&mut Transform, With<Camera>>,
+) {
+    for (transform, children) in window_query.iter() {
+        for &child in children.iter() {
+            if let Ok(mut camera) = cameras.get_mut(child) {
+                camera.rotation = transform.rotation.inverse();
+            }
+        }
+    }
+}
+
+// from the top left of the window to its center, in physics units
+fn center_offset(window: &winit::window::Window, converter: &CoordConverter) -> Vector<Real> {
+    let size = converter.from_physical_size(window.outer_size());
+    let size = converter.to_physics_vec(size);
+    Vector::from([size[0], -size[1]]) / 2.
+}
+
+// where the center of the window is in physics, from where the OS has put it. The body stands in
+// for the whole OS window, title bar and borders included. None where windows aren't told where
+// they are, on Wayland
+fn window_center(
+    window: &winit::window::Window,
+    converter: &CoordConverter,
+) -> Option<Point<Real>> {
+    let top_left = converter.from_physical(window.outer_position().ok()?);
+    Some(converter.to_physics_point(top_left) + center_offset(window, converter))
+}
+
+// where a new window's body starts when the OS won't say where the window is, the middle of the
+// first monitor
+fn fallback_center(converter: &CoordConverter) -> Point<Real> {
+    converter
+        .monitors
+        .first()
+        .map_or_else(Point::origin, |monitor| {
+            converter.to_physics_point(monitor.center())
+        })
+}
+
+// from the center of the window to the center of its content area, in physics units. The title
+// bar pushes the content below the window's center, so the walls and the camera are moved by this
+fn content_offset(window: &winit::window::Window, converter: &CoordConverter) -> Vector<Real> {
+    let (inner, outer) = match (window.inner_position(), window.outer_position()) {
+        (Ok(inner), Ok(outer)) => (
+            converter.from_physical(inner),
+            converter.from_physical(outer),
+        ),
+        _ => return Vector::zeros(),
+    };
+    let inner_size = converter.from_physical_size(window.inner_size());
+    let outer_size = converter.from_physical_size(window.outer_size());
+    let offset = converter.to_physics_vec(LogicalSize::new(
+        inner.x - outer.x + (inner_size.width - outer_size.width) / 2.,
+        inner.y - outer.y + (inner_size.height - outer_size.height) / 2.,
+    ));
+    // desktop y points down
+    Vector::new(offset[0], -offset[1])
+}
+
+// the sizes of a window the body and walls are built from, in physics units
+struct WindowFrame {
+    outer: Vector<Real>,
+    inner: Vector<Real>,
+    content_offset: Vector<Real>,
+}
+
+impl WindowFrame {
+    fn of(window: &winit::window::Window, converter: &CoordConverter) -> Self {
+        Self {
+            outer: converter.to_physics_vec(converter.from_physical_size(window.outer_size())),
+            inner: converter.to_physics_vec(converter.from_physical_size(window.inner_size())),
+            content_offset: content_offset(window, converter),
+        }
+    }
+}
+
+// the body standing in for an OS window, with its walls and camera as children, and its shapes
+#[allow(clippy::too_many_arguments)]
+fn spawn_window_body(
+    commands: &mut Commands,
+    frame: &WindowFrame,
+    physics_window: PhysicsWindow,
+    camera: OrthographicCameraBundle,
+    converter: &CoordConverter,
+    window_density: &WindowDensity,
+    spawn_config: &SpawnConfig,
+    weights: &ShapeWeights,
+    jelly: &JellyConfig,
+    rng: &mut StdRng,
+    center: Point<Real>,
+    linvel: Vector<Real>,
+) -> Entity {
+    let content_offset = frame.content_offset;
+    let mut camera = camera;
+    camera.transform.translation +=
+        Vec3::new(content_offset.x, content_offset.y, 0.) * converter.physics_scale;
+    let camera = commands
+        .spawn()
+        .insert_bundle(camera)
+        .insert(CameraZoom::default())
+        .id();
+
+    let walls = commands
+        .spawn_bundle(ColliderBundle {
+            shape: box_collider((frame.inner / 2.).into()).into(),
+            position: Isometry::new(content_offset, 0.).into(),
+            material: ColliderMaterial::new(0.8, 0.3).into(),
+            flags: ColliderFlags {
+                collision_groups: groups::window_walls(physics_window.interior),
+                ..Default::default()
+            }
+            .into(),
+            ..Default::default()
+        })
+        .insert(WindowWalls)
+        .id();
+
+    let body = commands
+        .spawn()
+        .insert_bundle(RigidBodyBundle {
+            body_type: RigidBodyType::KinematicPositionBased.into(),
+            position: Isometry::new(center.coords, 0.).into(),
+            mass_properties: RigidBodyMassPropsFlags::ROTATION_LOCKED.into(),
+            velocity: RigidBodyVelocity { linvel, angvel: 0. }.into(),
+            ..Default::default()
+        })
+        .insert_bundle(ColliderBundle {
+            shape: ColliderShape::cuboid(frame.outer[0] / 2., frame.outer[1] / 2.).into(),
+            mass_properties: ColliderMassProps::Density(window_density.0).into(),
+            material: ColliderMaterial::new(0.8, 0.3).into(),
+            flags: ColliderFlags {
+                // the windows collide with each other and the monitor edges
+                collision_groups: groups::window_body(),
+                active_events: ActiveEvents::CONTACT_EVENTS,
+                ..Default::default()
+            }
+            .into(),
+            ..Default::default()
+        })
+        .insert(RigidBodyPositionSync::default())
+        // restored windows start out static instead of flying off straight away
+        .insert(WindowState::default())
+        .insert(physics_window)
+        .insert(DragHistory::default())
+        .insert(DragCharge::default())
+        .insert(DragPress::default())
+        .insert(PlacedAt::default())
+        .insert(SlowFor::default())
+        .insert(BounceCount::default())
+        .add_child(walls)
+        .add_child(camera)
+        .id();
+
+    spawn_shapes(
+        commands,
+        converter,
+        spawn_config,
+        weights,
+        jelly,
+        rng,
+        (body, physics_window.interior),
+        center + content_offset,
+    );
+    body
+}
+
+#[allow(clippy::too_many_arguments)]
+fn setup(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    winit_windows: Option<Res<WinitWindows>>,
+    physics_scale: Res<PhysicsScale>,
+    spawn_config: Res<SpawnConfig>,
+    weights: Res<ShapeWeights>,
+    jelly: Res<JellyConfig>,
+    mut rng: ResMut<ShapeRng>,
+    window_density: Res<WindowDensity>,
+    window_count: Res<WindowCount>,
+    boundary_mode: Res<BoundaryMode>,
+    mut create_window_events: EventWriter<CreateWindow>,
+    headless: Option<Res<Headless>>,
+) {
+    if let Some(headless) = headless {
+        let converter = CoordConverter::headless(headless.monitor_size.into(), physics_scale.0);
+        commands.insert_resource(converter.clone());
+        let size = converter.to_physics_vec(headless.window_size.into());
+        let frame = WindowFrame {
+            outer: size,
+            inner: size,
+            content_offset: Vector::zeros(),
+        };
+        let primary = spawn_window_body(
+            &mut commands,
+            &frame,
+            PhysicsWindow {
+                id: WindowId::primary(),
+                interior: groups::interior(0),
+            },
+            OrthographicCameraBundle::new_2d(),
+            &converter,
+            &window_density,
+            &spawn_config,
+            &weights,
+            &jelly,
+            &mut rng.0,
+            converter.to_physics_point(converter.monitors[0].center()),
+            Vector::zeros(),
+        );
+        commands.entity(primary).insert(PrimaryWindow);
+        if window_count.0 > 1 {
+            warn!("Running headless, only the primary window is simulated");
+        }
+        spawn_monitor_bounds(&mut commands, &converter, *boundary_mode);
+        return;
+    }
+
+    let window = match windows
+        .get_primary()
+        .zip(winit_windows.as_deref())
+        .and_then(|(w, winit_windows)| winit_windows.get_window(w.id()))
+    {
+        Some(window) => window,
+        None => {
+            info!("No winit primary window, window physics is disabled");
+            return;
+        }
+    };
+    let converter = match CoordConverter::new(window, physics_scale.0) {
+        Some(converter) => converter,
+        None => {
+            info!("Primary window is on no monitor, window physics is disabled");
+            return;
+        }
+    };
+    commands.insert_resource(converter.clone());
+
+    let saved = SavedWindow::load();
+    let center = match &saved {
+        Some(saved) => {
+            let center = Point::from(saved.center);
+            let top_left = center - center_offset(window, &converter);
+            window.set_outer_position(
+                converter.to_physical(converter.to_logical_winit_position(top_left)),
+            );
+            center
+        }
+        None => window_center(window, &converter).unwrap_or_else(|| fallback_center(&converter)),
+    };
+
+    let primary = spawn_window_body(
+        &mut commands,
+        &WindowFrame::of(window, &converter),
+        PhysicsWindow {
+            id: WindowId::primary(),
+            interior: groups::interior(0),
+        },
+        OrthographicCameraBundle::new_2d(),
+        &converter,
+        &window_density,
+        &spawn_config,
+        &weights,
+        &jelly,
+        &mut rng.0,
+        center,
+        saved.as_ref().map_or(Vector::zeros(), |s| s.linvel.into()),
+    );
+    commands.entity(primary).insert(PrimaryWindow);
+
+    if window_count.0 > groups::MAX_WINDOWS {
+        warn!(
+            "WindowCount {} is more than the {} windows supported",
+            window_count.0,
+            groups::MAX_WINDOWS
+        );
+    }
+    let size = window.inner_size().to_logical(window.scale_factor());
+    open_extra_windows(
+        &mut commands,
+        &mut create_window_events,
+        window_count.0.min(groups::MAX_WINDOWS),
+        size,
+    );
+
+    spawn_monitor_bounds(&mut commands, &converter, *boundary_mode);
+}
+
+/// Runs the plugin without any OS windows, for tests and benchmarks. Insert this before adding
+/// `WindowPhysicsPlugin`.
+///
+/// The desktop is a single monitor of `monitor_size`, and the primary window is a body of
+/// `window_size` in its middle, both in logical pixels and without a title bar. Nothing is read
+/// from or sent to winit: bodies move as the physics moves them, and static and dragged windows
+/// stay where they are. Extra windows aren't opened.
+///
+/// The app still needs bevy's core, window, input and asset plugins, e.g. `MinimalPlugins` with
+/// `WindowPlugin`, `InputPlugin` and `AssetPlugin`, and no renderer or GPU is needed.
+pub struct Headless {
+    pub monitor_size: [Real; 2],
+    pub window_size: [Real; 2],
+}
+
+impl Default for Headless {
+    fn default() -> Self {
+        Self {
+            monitor_size: [1920., 1080.],
+            window_size: [600., 400.],
+        }
+    }
+}
+
+// setup only inserts the converter once it has found the primary window
+fn window_found(converter: Option<Res<CoordConverter>>) -> ShouldRun {
+    if converter.is_some() {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+/// Lets the desktop show through the window's background, toggled with T. The background keeps
+/// its tint, at `alpha`.
+///
+/// The windows have to be created with `WindowDescriptor::transparent` set for this to work, and
+/// even then it depends on the platform and graphics backend: Bevy 0.6 can't choose how the window
+/// is composited, so on some systems, Windows with DirectX 12 for one, the background stays
+/// opaque.
+pub struct Transparency {
+    pub enabled: bool,
+    pub alpha: f32,
+}
+
+impl Default for Transparency {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            alpha: 0.25,
+        }
+    }
+}
+
+fn t_toggles_transparency(keys: Res<Input<KeyCode>>, mut transparency: ResMut<Transparency>) {
+    if keys.just_pressed(KeyCode::T) {
+        transparency.enabled = !transparency.enabled;
+    }
+}
+
+/// The static window's grey slowly brightening and dimming by up to `depth` of itself, once every
+/// `period` seconds, so it doesn't look frozen. A `depth` of 0 keeps it flat.
+pub struct IdlePulse {
+    pub period: f32,
+    pub depth: f32,
+}
+
+impl Default for IdlePulse {
+    fn default() -> Self {
+        Self {
+            period: 4.,
+            depth: 0.15,
+        }
+    }
+}
+
+impl IdlePulse {
+    fn brightness(&self, seconds: f64) -> f32 {
+        if self.period <= 0. {
+            return 1.;
+        }
+        let phase = (seconds / self.period as f64).fract() as f32 * std::f32::consts::TAU;
+        1. + self.depth * phase.sin()
+    }
+}
+
+/// The bouncing window's navy flashing towards white when it hits a monitor edge, by up to
+/// `strength` for an impact at `full_speed` physics units per second or faster, and fading by
+/// `decay` of itself per second. A `strength` of 0 turns it off.
+pub struct ImpactFlash {
+    pub strength: f32,
+    pub full_speed: Real,
+    pub decay: f32,
+}
+
+impl Default for ImpactFlash {
+    fn default() -> Self {
+        Self {
+            strength: 0.6,
+            full_speed: 2.,
+            decay: 6.,
+        }
+    }
+}
+
+// how far the background currently is towards white, from 0 to 1
+#[derive(Default)]
+struct FlashIntensity(f32);
+
+// a harder hit than the one still fading replaces it, a softer one is lost in it
+fn primary_window_impacts_flash(
+    time: Res<Time>,
+    flash: Res<ImpactFlash>,
+    mut intensity: ResMut<FlashIntensity>,
+    mut contact_events: EventReader<ContactEvent>,
+    window: Query<&RigidBodyVelocityComponent, With<PrimaryWindow>>,
+    monitors: Query<Entity, With<MonitorBounds>>,
+) {
+    intensity.0 *= (-flash.decay * time.delta_seconds()).exp();
+    for event in contact_events.iter() {
+        let (e1, e2) = match event {
+            ContactEvent::Started(h1, h2) => (h1.entity(), h2.entity()),
+            ContactEvent::Stopped(_, _) => continue,
+        };
+        let velocity = match window.get(e1).or_else(|_| window.get(e2)) {
+            Ok(velocity) => velocity,
+            Err(_) => continue,
+        };
+        if monitors.get(e1).is_err() && monitors.get(e2).is_err() {
+            continue;
+        }
+        let hit = flash.strength * (velocity.linvel.norm() / flash.full_speed).min(1.);
+        intensity.0 = intensity.0.max(hit);
+    }
+}
+
+// a charging throw brightens from grey to orange, a static window breathes unless paused, and a
+// bouncing one flashes when it hits an edge
+#[allow(clippy::too_many_arguments)]
+fn window_background_indicates_state(
+    mut background: ResMut<ClearColor>,
+    window: Query<(&WindowState, &DragCharge), With<PrimaryWindow>>,
+    time: Res<Time>,
+    paused: Res<SimulationPaused>,
+    transparency: Res<Transparency>,
+    fling_config: Res<FlingConfig>,
+    pulse: Res<IdlePulse>,
+    flash: Res<FlashIntensity>,
+) {
+    let (window_state, charge) = match window.get_single() {
+        Ok(window) => window,
+        Err(e) => {
+            debug!("No primary window to colour: {}", e);
+            return;
+        }
+    };
+    let mut colour = if paused.0 {
+        Color::rgb(0.25, 0.15, 0.35)
+    } else {
+        match window_state {
+            WindowState::Bouncing => {
+                let [r, g, b, _] = Color::NAVY.as_rgba_f32();
+                let towards_white = |c: f32| c + (1. - c) * flash.0;
+                Color::rgb(towards_white(r), towards_white(g), towards_white(b))
+            }
+            WindowState::Dragging(_) => {
+                let charge = charge.fraction(&fling_config);
+                Color::rgb(
+                    0.25 + 0.75 * charge,
+                    0.25 + 0.15 * charge,
+                    0.25 - 0.25 * charge,
+                )
+            }
+            WindowState::Static => {
+                let grey = 0.5 * pulse.brightness(time.seconds_since_startup());
+                Color::rgb(grey, grey, grey)
+            }
+        }
+    };
+    colour.set_a(if transparency.enabled {
+        transparency.alpha
+    } else {
+        1.
+    });
+    *background = ClearColor(colour);
+}
+
+// moves `v` across the range if it has left it
+fn wrap(v: Real, min: Real, max: Real) -> Real {
+    if v < min {
+        v + (max - min)
+    } else if v > max {
+        v - (max - min)
+    } else {
+        v
+    }
+}
+
+/// Where each bouncing window's body was after the step before last and after the last one.
+#[derive(Default)]
+struct StepPositions(HashMap<Entity, [Vector<Real>; 2]>);
+
+// a bouncing OS window is placed between the body's last two step positions, as far along as the
+// time left over after the last step is into the next one, like rapier's interpolated transforms.
+// It trails the body by up to a step, but moves every frame instead of only on frames that took a
+// step
+#[allow(clippy::too_many_arguments)]
+fn update_physics_or_application_window(
+    mut window_query: Query<
+        (
+            Entity,
+            &PhysicsWindow,
+            &WindowState,
+            &mut RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+            &mut PlacedAt,
+        ),
+        Without<InteriorShape>,
+    >,
+    mut shapes: Query<(&InteriorShape, &mut RigidBodyPositionComponent), Without<WindowState>>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+    paused: Res<SimulationPaused>,
+    step: Res<StepRequest>,
+    boundary_mode: Res<BoundaryMode>,
+    on_screen_margin: Res<OnScreenMargin>,
+    sim_to_render_time: Res<SimulationToRenderTime>,
+    mut step_positions: ResMut<StepPositions>,
+) {
+    // a single step taken while paused is synced like any other, and shown where it ended
+    if paused.0 && !step.is_stepping() {
+        return;
+    }
+    let alpha = if step.is_stepping() {
+        1.
+    } else {
+        (sim_to_render_time.diff / PHYSICS_DT).clamp(0., 1.)
+    };
+    for (entity, physics_window, window_state, mut window_physics, mut velocity, mut placed_at) in
+        window_query.iter_mut()
+    {
+        let window = match winit_windows.get_window(physics_window.id) {
+            Some(window) => window,
+            None => continue,
+        };
+
+        match window_state {
+            WindowState::Bouncing => {
+                let mut shift = Vector::zeros();
+                if *boundary_mode == BoundaryMode::Wrap {
+                    let [min, max] = converter.desktop_physics_bounds();
+                    let center = window_physics.position.translation.vector;
+                    let wrapped =
+                        Vector::from([wrap(center.x, min.x, max.x), wrap(center.y, min.y, max.y)]);
+                    shift = wrapped - center;
+                }
+
+                // a window that got past the monitor edges, e.g. through a monitor being
+                // unplugged, is brought back within reach and sent back towards the desktop
+                let center = Point::from(window_physics.position.translation.vector + shift);
+                let top_left =
+                    converter.to_logical_winit_position(center - center_offset(window, &converter));
+                let on_screen = converter.keep_on_screen(
+                    top_left,
+                    converter.from_physical_size(window.outer_size()),
+                    on_screen_margin.0,
+                );
+                if on_screen != top_left {
+                    let back = converter.to_physics_point(on_screen)
+                        - converter.to_physics_point(top_left);
+                    for axis in 0..2 {
+                        if back[axis] * velocity.linvel[axis] < 0. {
+                            velocity.linvel[axis] = -velocity.linvel[axis];
+                        }
+                    }
+                    shift += back;
+                }
+
+                if shift != Vector::zeros() {
+                    // physics is moved first so the OS window is placed from the new position
+                    window_physics.position.translation.vector += shift;
+                    window_physics.next_position.translation.vector += shift;
+                    // the window's shapes come along so they stay inside its walls
+                    for (shape, mut position) in shapes.iter_mut() {
+                        if shape.window == entity {
+                            position.position.translation.vector += shift;
+                            position.next_position.translation.vector += shift;
+                        }
+                    }
+                    // and so does its last step, so a wrap isn't drawn out across the desktop
+                    if let Some(positions) = step_positions.0.get_mut(&entity) {
+                        positions.iter_mut().for_each(|p| *p += shift);
+                    }
+                }
+
+                // only the center is used, the OS window stays upright however the body is rotated
+                let current = window_physics.position.translation.vector;
+                let [previous, last] = step_positions.0.entry(entity).or_insert([current; 2]);
+                if *last != current {
+                    *previous = *last;
+                    *last = current;
+                }
+                let center: Point<_> = (*previous + (*last - *previous) * alpha).into();
+
+                let top_left = center - center_offset(window, &converter);
+
+                let position = converter.to_physical(converter.to_logical_winit_position(top_left));
+                // moving the OS window flickers on some platforms, and a window at rest would
+                // otherwise be moved to where it already is every frame
+                if placed_at.0 != Some(position) {
+                    window.set_outer_position(position);
+                    placed_at.0 = Some(position);
+                }
+            }
+            // a dragged window is moved by the cursor, so it is followed like a static one
+            WindowState::Static | WindowState::Dragging(_) => {
+                // the OS window is moved by someone else now
+                placed_at.0 = None;
+                step_positions.0.remove(&entity);
+
+                // held windows are stood back upright. One that can't tell where it is stays put
+                let center = window_center(window, &converter)
+                    .unwrap_or_else(|| window_physics.position.translation.vector.into());
+                window_physics.next_position = Isometry::new(center.coords, 0.0);
+            }
+        }
+    }
+}
+
+// how often the drift between the bodies and their OS windows is logged, in seconds
+const DRIFT_LOG_PERIOD: f64 = 1.;
+
+// some OSes clamp or snap where a window can be moved, e.g. keeping it off a panel, so a bouncing
+// body can end up somewhere other than its OS window, and is then placed back against the clamp
+// every frame, jittering. Once the OS window has settled somewhere other than where it was placed,
+// the body and its shapes are moved to where it really is, and the body stops moving into the
+// clamp, as if it had hit a wall. A window that hasn't caught up yet is still moving, so it is
+// left alone until it stops.
+#[allow(clippy::too_many_arguments)]
+fn body_follows_os_clamping(
+    time: Res<Time>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+    paused: Res<SimulationPaused>,
+    mut window_query: Query<
+        (
+            Entity,
+            &PhysicsWindow,
+            &WindowState,
+            &mut RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+            &mut PlacedAt,
+        ),
+        Without<InteriorShape>,
+    >,
+    mut shapes: Query<(&InteriorShape, &mut RigidBodyPositionComponent), Without<WindowState>>,
+    mut last_seen: Local<HashMap<Entity, PhysicalPosition<i32>>>,
+    mut drift_total: Local<(Real, f64)>, // physics units, and when it was last logged
+) {
+    let now = time.seconds_since_startup();
+    if now - drift_total.1 >= DRIFT_LOG_PERIOD {
+        if drift_total.0 > 0. {
+            debug!(
+                "Windows drifted from their bodies by {:.4} physics units per second",
+                drift_total.0 as f64 / (now - drift_total.1)
+            );
+        }
+        *drift_total = (0., now);
+    }
+    if paused.0 {
+        return;
+    }
+
+    for (entity, physics_window, window_state, mut position, mut velocity, mut placed_at) in
+        window_query.iter_mut()
+    {
+        let actual = match winit_windows
+            .get_window(physics_window.id)
+            .and_then(|window| window.outer_position().ok())
+        {
+            Some(actual) => actual,
+            None => continue,
+        };
+        let settled = last_seen.insert(entity, actual) == Some(actual);
+        let placed = match (window_state, placed_at.0) {
+            (WindowState::Bouncing, Some(placed)) if settled && placed != actual => placed,
+            _ => continue,
+        };
+
+        let drift = converter.to_physics_point(converter.from_physical(actual))
+            - converter.to_physics_point(converter.from_physical(placed));
+        drift_total.0 += drift.norm();
+        for axis in 0..2 {
+            if drift[axis] * velocity.linvel[axis] < 0. {
+                velocity.linvel[axis] = 0.;
+            }
+        }
+        position.position.translation.vector += drift;
+        position.next_position.translation.vector += drift;
+        for (shape, mut position) in shapes.iter_mut() {
+            if shape.window == entity {
+                position.position.translation.vector += drift;
+                position.next_position.translation.vector += drift;
+            }
+        }
+        // already there, so it isn't moved back
+        placed_at.0 = Some(actual);
+    }
+}
+
+// the window passes through the monitor edges when wrapping
+fn apply_boundary_mode(
+    boundary_mode: Res<BoundaryMode>,
+    mut monitors: Query<&mut ColliderFlagsComponent, With<MonitorBounds>>,
+) {
+    if boundary_mode.is_changed() {
+        for mut flags in monitors.iter_mut() {
+            flags.collision_groups = boundary_mode.monitor_groups();
+        }
+    }
+}
+
+fn apply_physics_scale(
+    physics_scale: Res<PhysicsScale>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut converter: ResMut<CoordConverter>,
+) {
+    if physics_scale.is_changed() {
+        rapier_config.scale = physics_scale.0;
+        converter.physics_scale = physics_scale.0;
+    }
+}
+
+fn apply_window_density(
+    window_density: Res<WindowDensity>,
+    mut window_query: Query<&mut ColliderMassPropsComponent, With<WindowState>>,
+) {
+    if window_density.is_changed() {
+        for mut mass_props in window_query.iter_mut() {
+            *mass_props = ColliderMassProps::Density(window_density.0).into();
+        }
+    }
+}
+
+fn window_physics_type_update(
+    mut window_query: Query<(&WindowState, &mut RigidBodyTypeComponent), Changed<WindowState>>,
+) {
+    for (window, mut rbtype) in window_query.iter_mut() {
+        *rbtype = match window {
+            WindowState::Bouncing => RigidBodyType::Dynamic,
+            WindowState::Static | WindowState::Dragging(_) => RigidBodyType::KinematicPositionBased,
+        }
+        .into()
+    }
+}
+
+/// Keeps the content area of the windows at this width to height ratio when they're resized, so
+/// the space the shapes have stays the same shape. Unset, they can be resized freely.
+#[derive(Default)]
+pub struct AspectLock(pub Option<f32>);
+
+// resizing to the locked ratio sends another WindowResized, which is already at the ratio and so
+// left alone. A pixel of slack keeps the OS rounding the size from setting off another resize
+fn resize_keeps_aspect_ratio(
+    mut resized_events: EventReader<WindowResized>,
+    aspect_lock: Res<AspectLock>,
+    window_query: Query<&PhysicsWindow>,
+    winit_windows: Res<WinitWindows>,
+) {
+    let ratio = match aspect_lock.0 {
+        Some(ratio) if ratio > 0. => ratio,
+        _ => return,
+    };
+    for event in resized_events.iter() {
+        if !window_query.iter().any(|w| w.id == event.id) {
+            continue;
+        }
+        let (width, height) = (event.width, event.height);
+        // whichever side has to change less is changed
+        let size = if (width / ratio - height).abs() <= (height * ratio - width).abs() {
+            LogicalSize::new(width, width / ratio)
+        } else {
+            LogicalSize::new(height * ratio, height)
+        };
+        if (size.width - width).abs() < 1. && (size.height - height).abs() < 1. {
+            continue;
+        }
+        if let Some(window) = winit_windows.get_window(event.id) {
+            window.set_inner_size(size);
+        }
+    }
+}
+
+// several resizes can arrive in one frame while the window is dragged larger, only the last one
+// of each window counts
+fn latest_resizes<'a>(
+    events: impl Iterator<Item = &'a WindowResized>,
+) -> HashMap<WindowId, LogicalSize<f32>> {
+    events
+        .map(|event| (event.id, LogicalSize::new(event.width, event.height)))
+        .collect()
+}
+
+// half the size of the walls inside a window of `inner_size` logical pixels at `scale_factor`, in
+// physics units
+fn walls_half_extents(
+    inner_size: LogicalSize<f32>,
+    scale_factor: f64,
+    converter: &CoordConverter,
+) -> Vector<Real> {
+    let inner_size = inner_size.to_physical(scale_factor);
+    converter.to_physics_vec(converter.from_physical_size(inner_size)) / 2.
+}
+
+// walls follow the inner size reported by the event, the body follows the outer size
+fn resize_update(
+    mut resized_events: EventReader<WindowResized>,
+    mut walls_query: Query<&mut ColliderShapeComponent, (With<WindowWalls>, Without<WindowState>)>,
+    mut window_query: Query<
+        (&PhysicsWindow, &Children, &mut ColliderShapeComponent),
+        (With<WindowState>, Without<WindowWalls>),
+    >,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+) {
+    for (id, inner_size) in latest_resizes(resized_events.iter()) {
+        // other windows belong to the host app
+        let (_, children, mut shape) = match window_query
+            .iter_mut()
+            .find(|(physics_window, _, _)| physics_window.id == id)
+        {
+            Some(window) => window,
+            None => continue,
+        };
+        // the window may have been closed since
+        let window = match winit_windows.get_window(id) {
+            Some(window) => window,
+            None => continue,
+        };
+
+        let half_extents = walls_half_extents(inner_size, window.scale_factor(), &converter);
+        for &child in children.iter() {
+            if let Ok(mut walls) = walls_query.get_mut(child) {
+                *walls = box_collider(half_extents.into()).into();
+            }
+        }
+
+        let outer_size = converter.from_physical_size(window.outer_size());
+        let halfbounds = converter.to_physics_vec(outer_size) / 2.;
+        *shape = ColliderShape::cuboid(halfbounds[0], halfbounds[1]).into();
+    }
+}
+
+// puts the windows back in the middle of their monitors with fresh sets of shapes, keeping the
+// cameras and the monitor colliders
+#[allow(clippy::too_many_arguments)]
+fn r_resets_scene(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+    spawn_config: Res<SpawnConfig>,
+    weights: Res<ShapeWeights>,
+    jelly: Res<JellyConfig>,
+    mut rng: ResMut<ShapeRng>,
+    shapes: Query<Entity, With<InteriorShape>>,
+    mut window_query: Query<(
+        Entity,
+        &PhysicsWindow,
+        &mut WindowState,
+        &mut RigidBodyPositionComponent,
+        &mut RigidBodyVelocityComponent,
+        &mut DragHistory,
+    )>,
+) {
+    if !keys.just_pressed(KeyCode::R) {
+        return;
+    }
+
+    for shape in shapes.iter() {
+        commands.entity(shape).despawn_recursive();
+    }
+
+    for (i, (entity, physics_window, mut window_state, mut position, mut velocity, mut history)) in
+        window_query.iter_mut().enumerate()
+    {
+        let window = match winit_windows.get_window(physics_window.id) {
+            Some(window) => window,
+            None => continue,
+        };
+        let monitor = match window.current_monitor() {
+            Some(monitor) => MonitorRect::new(&monitor, converter.desktop_scale),
+            None => continue,
+        };
+
+        // windows on the same monitor are cascaded so they don't start inside each other
+        let cascade = converter.to_physics_vec(LogicalSize::new(30., -30.)) * i as Real;
+        let center = converter.to_physics_point(monitor.center()) + cascade;
+        let top_left = center - center_offset(window, &converter);
+        window.set_outer_position(
+            converter.to_physical(converter.to_logical_winit_position(top_left)),
+        );
+
+        *window_state = WindowState::Static;
+        position.position = Isometry::new(center.coords, 0.);
+        position.next_position = position.position;
+        velocity.linvel = Vector::zeros();
+        velocity.angvel = 0.;
+        *history = DragHistory::default();
+
+        spawn_shapes(
+            &mut commands,
+            &converter,
+            &spawn_config,
+            &weights,
+            &jelly,
+            &mut rng.0,
+            (entity, physics_window.interior),
+            center + content_offset(window, &converter),
+        );
+    }
+}
+
+fn toggle_physics_on_spacebar(keys: Res<Input<KeyCode>>, mut window: Query<&mut WindowState>) {
+    if keys.just_pressed(KeyCode::Space) {
+        for mut window in window.iter_mut() {
+            window.toggle_physics();
+        }
+    }
+}
+
+/// How far, in desktop units, WASD nudge a static window, see [`BatImpulse`] for bouncing ones.
+/// Holding shift moves it `SHIFT_FACTOR` times as far.
+pub struct NudgeStep(pub Real);
+
+impl Default for NudgeStep {
+    fn default() -> Self {
+        Self(5.)
+    }
+}
+
+impl NudgeStep {
+    const SHIFT_FACTOR: Real = 10.;
+}
+
+// only the OS window is moved, the body follows it like it follows any static window
+fn wasd_nudges_static_window(
+    keys: Res<Input<KeyCode>>,
+    nudge_step: Res<NudgeStep>,
+    window_query: Query<(&PhysicsWindow, &WindowState)>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+) {
+    let direction = [
+        (KeyCode::A, [-1., 0.]),
+        (KeyCode::D, [1., 0.]),
+        (KeyCode::W, [0., -1.]),
+        (KeyCode::S, [0., 1.]),
+    ]
+    .into_iter()
+    .filter(|(key, _)| keys.just_pressed(*key))
+    .fold([0., 0.], |[x, y], (_, [dx, dy])| [x + dx, y + dy]);
+    if direction == [0., 0.] {
+        return;
+    }
+    let step = if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        nudge_step.0 * NudgeStep::SHIFT_FACTOR
+    } else {
+        nudge_step.0
+    };
+
+    for (physics_window, window_state) in window_query.iter() {
+        if !matches!(window_state, WindowState::Static) {
+            continue;
+        }
+        let window = match winit_windows.get_window(physics_window.id) {
+            Some(window) => window,
+            None => continue,
+        };
+        if let Ok(outer) = window.outer_position() {
+            let top_left = converter.from_physical(outer);
+            window.set_outer_position(converter.to_physical(LogicalPosition::new(
+                top_left.x + direction[0] * step,
+                top_left.y + direction[1] * step,
+            )));
+        }
+    }
+}
+
+/// The impulse WASD give a bouncing window on each press, batting it about like a balloon, in the
+/// same units as a fling's. Not the arrow keys, which turn gravity.
+pub struct BatImpulse(pub Real);
+
+impl Default for BatImpulse {
+    fn default() -> Self {
+        Self(0.3)
+    }
+}
+
+// a tap each press, unlike the gamepad's steady push. Static windows are nudged by the same keys
+fn wasd_bats_bouncing_window(
+    keys: Res<Input<KeyCode>>,
+    bat_impulse: Res<BatImpulse>,
+    mut window_query: Query<(
+        &WindowState,
+        &mut RigidBodyVelocityComponent,
+        &RigidBodyMassPropsComponent,
+        &mut RigidBodyActivationComponent,
+    )>,
+) {
+    let direction = [
+        (KeyCode::A, -Vector::x()),
+        (KeyCode::D, Vector::x()),
+        (KeyCode::W, Vector::y()),
+        (KeyCode::S, -Vector::y()),
+    ]
+    .into_iter()
+    .filter(|(key, _)| keys.just_pressed(*key))
+    .fold(Vector::zeros(), |sum, (_, direction)| sum + direction);
+    if direction == Vector::zeros() {
+        return;
+    }
+
+    for (window_state, mut velocity, rbmp, mut activation) in window_query.iter_mut() {
+        if matches!(window_state, WindowState::Bouncing) {
+            velocity.apply_impulse(rbmp, direction.normalize() * bat_impulse.0);
+            activation.wake_up(true);
+        }
+    }
+}
+
+/// Gravity applied to the window and the shapes, in physics units.
+pub struct GravityControl(pub Vector<Real>);
+
+impl Default for GravityControl {
+    fn default() -> Self {
+        Self(RapierConfiguration::default().gravity)
+    }
+}
+
+fn arrow_keys_rotate_gravity(keys: Res<Input<KeyCode>>, mut gravity: ResMut<GravityControl>) {
+    let direction = if keys.just_pressed(KeyCode::Up) {
+        Vector::y()
+    } else if keys.just_pressed(KeyCode::Down) {
+        -Vector::y()
+    } else if keys.just_pressed(KeyCode::Left) {
+        -Vector::x()
+    } else if keys.just_pressed(KeyCode::Right) {
+        Vector::x()
+    } else {
+        return;
+    };
+    gravity.0 = direction * gravity.0.norm();
+}
+
+// gravity only acts on dynamic bodies, so a window that is being dragged picks up nothing to
+// release later
+// planet gravity stands in for it while it's on
+fn apply_gravity(
+    gravity: Res<GravityControl>,
+    planet: Res<PlanetGravity>,
+    wind: Res<Wind>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut bodies: Query<&mut RigidBodyActivationComponent>,
+) {
+    rapier_config.gravity = if planet.enabled {
+        Vector::zeros()
+    } else {
+        gravity.0
+    };
+    if gravity.is_changed() || planet.is_changed() || wind.is_changed() {
+        // resting bodies are asleep and would ignore the new direction
+        for mut activation in bodies.iter_mut() {
+            activation.wake_up(true);
+        }
+    }
+}
+
+/// Makes the primary window, and any extra windows asked for with [`WindowCount`], physics bodies
+/// with shapes inside them.
+///
+/// Does nothing if the app has no winit primary window, e.g. when running headless.
+///
+/// The settings given to the builder methods override resources inserted beforehand, anything
+/// left unset keeps the resource's value or its default.
+///
+/// The arrow keys turn gravity and WASD nudge a static window, so a few features are on other
+/// keys than their names suggest:
+/// - O keeps the windows on top, see [`AlwaysOnTop`]
+/// - U toggles their title bars and borders, see [`Decorations`]
+/// - WASD rather than the arrow keys bat a bouncing window, see [`BatImpulse`]
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_rapier2d::prelude::*;
+/// # use window_dot_velocity::WindowPhysicsPlugin;
+/// App::new().add_plugin(
+///     WindowPhysicsPlugin::new()
+///         .with_shape_count(20)
+///         .with_gravity(Vector::y() * -9.8)
+///         .with_pixels_per_meter(750.),
+/// );
+/// ```
+#[derive(Default)]
+pub struct WindowPhysicsPlugin {
+    shape_count: Option<usize>,
+    gravity: Option<Vector<Real>>,
+    physics_scale: Option<Real>,
+}
+
+impl WindowPhysicsPlugin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// shapes put in each window at startup, see [`SpawnConfig::count`]
+    pub fn with_shape_count(mut self, count: usize) -> Self {
+        self.shape_count = Some(count);
+        self
+    }
+
+    /// see [`GravityControl`]
+    pub fn with_gravity(mut self, gravity: Vector<Real>) -> Self {
+        self.gravity = Some(gravity);
+        self
+    }
+
+    /// pixels per physics unit, see [`PhysicsScale`]
+    pub fn with_pixels_per_meter(mut self, pixels: Real) -> Self {
+        self.physics_scale = Some(pixels);
+        self
+    }
+}
+
+impl Plugin for WindowPhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(scale) = self.physics_scale {
+            app.insert_resource(PhysicsScale(scale));
+        }
+        if let Some(gravity) = self.gravity {
+            app.insert_resource(GravityControl(gravity));
+        }
+        if let Some(count) = self.shape_count {
+            app.world
+                .get_resource_or_insert_with(SpawnConfig::default)
+                .count = count;
+        }
+
+        if app.world.contains_resource::<Headless>() {
+            // with no OS windows in it, every system that looks one up finds none and skips it
+            app.init_resource::<WinitWindows>();
+            // resources the renderer, audio and diagnostics plugins would add, in case the app
+            // goes without them
+            app.init_resource::<ClearColor>()
+                .init_resource::<ActiveCameras>()
+                .init_resource::<Audio>()
+                .init_resource::<Diagnostics>();
+        }
+
+        let rng = ShapeRng::new(&app.world.get_resource_or_insert_with(SpawnSeed::default));
+        let physics_scale = *app.world.get_resource_or_insert_with(PhysicsScale::default);
+        app.insert_resource(RapierConfiguration {
+            scale: physics_scale.0,
+            timestep_mode: TimestepMode::InterpolatedTimestep,
+            ..Default::default()
+        })
+        .init_resource::<GravityControl>()
+        .init_resource::<SpawnConfig>()
+        .init_resource::<ShapeWeights>()
+        .insert_resource(rng)
+        .init_resource::<WindowCount>()
+        .init_resource::<WindowDensity>()
+        .init_resource::<RotationEnabled>()
+        .init_resource::<FlingConfig>()
+        .init_resource::<FlingPreview>()
+        .init_resource::<DemoFling>()
+        .init_resource::<DragThreshold>()
+        .init_resource::<DragDeadZone>()
+        .init_resource::<SimulationPaused>()
+        .init_resource::<StepRequest>()
+        .init_resource::<StepsThisFrame>()
+        .init_resource::<TimeScale>()
+        .init_resource::<BoundaryMode>()
+        .init_resource::<Bounciness>()
+        .init_resource::<StepPositions>()
+        .init_resource::<RestitutionFalloff>()
+        .init_resource::<ContactFriction>()
+        .init_resource::<ContinuousCollision>()
+        .init_resource::<AirDrag>()
+        .init_resource::<SnapGrid>()
+        .init_resource::<DebugRender>()
+        .init_resource::<ExplosionConfig>()
+        .init_resource::<CollisionStats>()
+        .init_resource::<OnScreenMargin>()
+        .init_resource::<NudgeStep>()
+        .init_resource::<WellConfig>()
+        .init_resource::<VacuumConfig>()
+        .init_resource::<PlanetGravity>()
+        .init_resource::<Transparency>()
+        .init_resource::<Decorations>()
+        .init_resource::<IdlePulse>()
+        .init_resource::<ImpactFlash>()
+        .init_resource::<FlashIntensity>()
+        .init_resource::<AspectLock>()
+        .init_resource::<ConfettiConfig>()
+        .init_resource::<BowlingConfig>()
+        .init_resource::<ShapeLimit>()
+        .init_resource::<NextSpawnIndex>()
+        .init_resource::<FreezeConfig>()
+        .init_resource::<ZeroGInterior>()
+        .init_resource::<ShapesRoam>()
+        .init_resource::<SleepConfig>()
+        .init_resource::<PhysicsDump>()
+        .init_resource::<OrbitConfig>()
+        .init_resource::<BatImpulse>()
+        .init_resource::<SparkConfig>()
+        .init_resource::<ShatterConfig>()
+        .init_resource::<ShapeRender>()
+        .init_resource::<TextureConfig>()
+        .init_resource::<EscapeConfig>()
+        .init_resource::<WallMagnet>()
+        .init_resource::<Wind>()
+        .init_resource::<ColorMode>()
+        .init_resource::<Recorder>()
+        .init_resource::<JellyConfig>()
+        .init_resource::<AssistConfig>()
+        .init_resource::<AlwaysOnTop>()
+        .init_resource::<SoundConfig>()
+        .init_resource::<TrailConfig>()
+        .init_resource::<PaintMode>()
+        .init_resource::<Canvas>()
+        .init_resource::<GamepadConfig>()
+        .init_resource::<ActiveGamepad>()
+        .init_resource::<EdgeMagnetism>()
+        .init_resource::<ScreenshotConfig>()
+        .init_resource::<ScreenshotRequest>()
+        .add_event::<WindowStateChanged>()
+        .add_event::<WindowBounced>()
+        .add_startup_system(setup)
+        .add_startup_system(load_bounce_sounds)
+        .add_startup_system(build_state_icons)
+        .add_startup_system(spawn_diagnostics_overlay)
+        .add_startup_system(spawn_wind_indicator)
+        .add_startup_system(spawn_minimap)
+        .add_system_set(
+            SystemSet::new()
+                .with_run_criteria(window_found)
+                .with_system(spawn_extra_window_bodies)
+                .with_system(update_physics_or_application_window.after(PhysicsSystems::StepWorld))
+                .with_system(body_follows_os_clamping)
+                .with_system(resize_update)
+                .with_system(resize_keeps_aspect_ratio)
+                .with_system(window_physics_type_update)
+                .with_system(send_window_state_changes)
+                .with_system(send_window_bounces)
+                .with_system(bounces_throw_sparks)
+                .with_system(collisions_shatter_shapes)
+                .with_system(sprites_replace_fills)
+                .with_system(toggle_physics_on_spacebar)
+                .with_system(wasd_nudges_static_window)
+                .with_system(wasd_bats_bouncing_window)
+                .with_system(r_resets_scene)
+                .with_system(pressing_grabs_window)
+                .with_system(dragged_window_follows_cursor)
+                .with_system(record_drag_history)
+                .with_system(dragging_flings_window)
+                .with_system(dragged_window_previews_fling)
+                .with_system(x_flings_window_to_corner)
+                .with_system(cursor_near_window_slows_it)
+                .with_system(primary_window_impacts_flash)
+                .with_system(window_background_indicates_state)
+                .with_system(window_icon_follows_state)
+                .with_system(t_toggles_transparency)
+                .with_system(arrow_keys_rotate_gravity)
+                .with_system(apply_gravity)
+                .with_system(apply_physics_scale)
+                .with_system(apply_window_density)
+                .with_system(apply_rotation_enabled)
+                .with_system(camera_stays_upright)
+                .with_system(n_spawns_shape_at_cursor)
+                .with_system(b_drops_bowling_ball)
+                .with_system(index_new_shapes)
+                .with_system(shift_scroll_changes_shape_count)
+                .with_system(z_freezes_shapes)
+                .with_system(digit_0_toggles_zero_g)
+                .with_system(apply_zero_g_interior)
+                .with_system(tab_toggles_roaming_shapes)
+                .with_system(apply_shape_roaming)
+                .with_system(right_click_deletes_shape)
+                .with_system(e_explodes_at_cursor)
+                .with_system(c_bursts_confetti)
+                .with_system(expired_shapes_despawn)
+                .with_system(escaped_shapes_drain)
+                .with_system(m_toggles_wall_magnet)
+                .with_system(shapes_cling_to_walls)
+                .with_system(mouse_wheel_zooms_window)
+                .with_system(home_resets_zoom)
+                .with_system(camera_matches_monitor_scale)
+                .with_system(ijkl_change_wind)
+                .with_system(show_wind)
+                .with_system(f4_toggles_minimap)
+                .with_system(minimap_shows_monitors)
+                .with_system(minimap_follows_window)
+                .with_system(colour_shapes)
+                .with_system(function_keys_control_recorder)
+                .with_system(record_window)
+                .with_system(replay_window)
+                .with_system(save_scene)
+                .with_system(load_scene)
+                .with_system(count_collisions)
+                .with_system(delete_resets_collision_stats)
+                .with_system(toggle_pause_on_p)
+                .with_system(
+                    period_steps_paused_physics
+                        .label(StepControl)
+                        .before(PhysicsSystems::StepWorld),
+                )
+                .with_system(plus_minus_change_time_scale)
+                .with_system(
+                    apply_time_scale
+                        .label(OweTime)
+                        .after(StepControl)
+                        .before(PhysicsSystems::StepWorld),
+                )
+                .with_system(title_shows_status)
+                .with_system(o_toggles_always_on_top)
+                .with_system(apply_always_on_top)
+                .with_system(u_toggles_decorations)
+                .with_system(apply_decorations)
+                .with_system(frame_follows_decorations)
+                .with_system(apply_boundary_mode)
+                .with_system(monitor_changes_refresh_bounds)
+                .with_system(brackets_change_bounciness)
+                .with_system(apply_bounciness)
+                .with_system(bounces_wear_restitution)
+                .with_system(apply_contact_friction)
+                .with_system(apply_continuous_collision)
+                .with_system(apply_sleep_config)
+                .with_system(f2_dumps_physics_world)
+                .with_system(f_toggles_air_drag)
+                .with_system(apply_air_drag)
+                .with_system(resting_window_goes_static)
+                .with_system(stopping_window_snaps_to_grid)
+                .with_system(window_hitting_monitor_plays_sound)
+                .with_system(draw_trails)
+                .with_system(keys_control_paint)
+                .with_system(shapes_paint_dots)
+                .with_system(track_gamepad)
+                .with_system(reset_forces.label(ResetForces))
+                .with_system(gamepad_pushes_window.label(AddForces).after(ResetForces))
+                .with_system(edges_attract_window.label(AddForces).after(ResetForces))
+                .with_system(window_orbits_cursor.label(AddForces).after(ResetForces))
+                .with_system(g_pulls_shapes_to_cursor.label(AddForces).after(ResetForces))
+                .with_system(
+                    v_vacuums_shapes_to_center
+                        .label(AddForces)
+                        .after(ResetForces),
+                )
+                .with_system(
+                    shapes_fall_towards_cursor
+                        .label(AddForces)
+                        .after(ResetForces),
+                )
+                .with_system(wind_pushes_bodies.label(AddForces).after(ResetForces))
+                .with_system(
+                    forces_last_every_step
+                        .after(AddForces)
+                        .after(OweTime)
+                        .before(PhysicsSystems::StepWorld),
+                )
+                .with_system(gamepad_toggles_physics)
+                .with_system(gamepad_brakes_window)
+                .with_system(f12_takes_screenshot)
+                .with_system(f3_toggles_diagnostics)
+                .with_system(update_diagnostics)
+                .with_system(f1_toggles_debug_render)
+                .with_system(draw_collider_outlines),
+        )
+        .add_plugin(MultiWindowRenderPlugin)
+        .add_plugin(ScreenshotRenderPlugin)
+        // after the window plugin has turned a close request into AppExit
+        .add_system_to_stage(CoreStage::Last, save_window_on_exit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::AssetPlugin, input::InputPlugin, window::WindowPlugin};
+
+    use super::*;
+
+    // no renderer and no winit, so there's no primary window for anything to find
+    fn headless_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(Headless::default())
+            .add_plugins(MinimalPlugins)
+            .add_plugin(WindowPlugin::default())
+            .add_plugin(InputPlugin)
+            .add_plugin(AssetPlugin)
+            .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+            .add_plugin(WindowPhysicsPlugin::new());
+        app
+    }
+
+    #[test]
+    fn only_the_last_resize_of_a_window_counts() {
+        let [a, b] = [WindowId::new(), WindowId::new()];
+        let resized = |id, width, height| WindowResized { id, width, height };
+        let events = [
+            resized(a, 100., 100.),
+            resized(b, 200., 100.),
+            resized(a, 300., 200.),
+        ];
+        let latest = latest_resizes(events.iter());
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[&a], LogicalSize::new(300., 200.));
+        assert_eq!(latest[&b], LogicalSize::new(200., 100.));
+    }
+
+    #[test]
+    fn resized_walls_are_half_the_new_size() {
+        let converter = CoordConverter::headless(LogicalSize::new(1920., 1080.), 1000.);
+        let half_extents = walls_half_extents(LogicalSize::new(300., 200.), 1., &converter);
+        assert!((half_extents - Vector::new(0.15, 0.1)).norm() < 1e-6);
+        // a window on a monitor scaled twice as much as the desktop is twice as big in physics
+        let half_extents = walls_half_extents(LogicalSize::new(300., 200.), 2., &converter);
+        assert!((half_extents - Vector::new(0.3, 0.2)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn headless_app_runs() {
+        let mut app = headless_app();
+        for _ in 0..5 {
+            app.update();
+        }
+        let mut windows = app
+            .world
+            .query_filtered::<&RigidBodyPositionComponent, With<PrimaryWindow>>();
+        assert_eq!(windows.iter(&app.world).count(), 1);
+    }
+}
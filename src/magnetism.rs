@@ -0,0 +1,218 @@
+use bevy::{prelude::*, winit::WinitWindows};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    coords::{cursor_to_physics, CoordConverter, Side},
+    shapes::InteriorShape,
+    simulation::{StepsThisFrame, PHYSICS_DT},
+    PhysicsWindow, WindowState,
+};
+
+/// Pulls a slowly bouncing window towards a nearby monitor edge, like a soft magnet. Off by
+/// default.
+pub struct EdgeMagnetism {
+    pub enabled: bool,
+    pub strength: Real, // force right at the edge, fading out to the capture distance
+    pub capture_distance: Real, // physics units between the window and the edge
+    pub release_speed: Real, // physics units per second, faster windows bounce freely
+}
+
+impl Default for EdgeMagnetism {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strength: 0.5,
+            capture_distance: 0.03,
+            release_speed: 0.3,
+        }
+    }
+}
+
+/// Label of the system that clears the window and shape forces each frame, anything pushing them
+/// adds to the force after it.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ResetForces;
+
+// forces are added to, so they are cleared first and letting go of a push stops it
+pub(crate) fn reset_forces(
+    mut bodies: Query<
+        &mut RigidBodyForcesComponent,
+        Or<(With<PhysicsWindow>, With<InteriorShape>)>,
+    >,
+) {
+    for mut forces in bodies.iter_mut() {
+        forces.force = Vector::zeros();
+    }
+}
+
+/// Label of the systems adding to the window and shape forces.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct AddForces;
+
+// rapier clears the forces after every step, so of the frame's steps only the first is pushed.
+// The rest get theirs up front as velocity, the same change in speed the force would have made
+pub(crate) fn forces_last_every_step(
+    steps: Res<StepsThisFrame>,
+    mut bodies: Query<
+        (
+            &RigidBodyTypeComponent,
+            &RigidBodyForcesComponent,
+            &RigidBodyMassPropsComponent,
+            &RigidBodyActivationComponent,
+            &mut RigidBodyVelocityComponent,
+        ),
+        Or<(With<PhysicsWindow>, With<InteriorShape>)>,
+    >,
+) {
+    if steps.0 < 2 {
+        return;
+    }
+    let extra_time = PHYSICS_DT * (steps.0 - 1) as Real;
+    for (body_type, forces, mass_props, activation, mut velocity) in bodies.iter_mut() {
+        if body_type.is_dynamic() && !activation.sleeping && forces.force != Vector::zeros() {
+            velocity.linvel += forces.force * mass_props.effective_inv_mass * extra_time;
+        }
+    }
+}
+
+pub(crate) fn edges_attract_window(
+    magnetism: Res<EdgeMagnetism>,
+    converter: Res<CoordConverter>,
+    mut window_query: Query<(
+        &WindowState,
+        &RigidBodyPositionComponent,
+        &RigidBodyVelocityComponent,
+        &ColliderShapeComponent,
+        &mut RigidBodyForcesComponent,
+        &mut RigidBodyActivationComponent,
+    )>,
+) {
+    if !magnetism.enabled {
+        return;
+    }
+    for (window_state, position, velocity, shape, mut forces, mut activation) in
+        window_query.iter_mut()
+    {
+        if !matches!(window_state, WindowState::Bouncing)
+            || velocity.linvel.norm() > magnetism.release_speed
+        {
+            continue;
+        }
+        let half = match shape.as_cuboid() {
+            Some(cuboid) => cuboid.half_extents,
+            None => continue,
+        };
+        let center = Point::from(position.position.translation.vector);
+
+        let pull = converter.monitors.iter().find_map(|monitor| {
+            let [min, max] = converter.monitor_physics_bounds(monitor);
+            let inside = (min.x..=max.x).contains(&center.x) && (min.y..=max.y).contains(&center.y);
+            if !inside {
+                return None;
+            }
+            // gaps between the window's sides and the monitor's, edges shared with another
+            // monitor can be crossed so they don't attract
+            [
+                (Side::Left, center.x - half.x - min.x, Vector::new(-1., 0.)),
+                (Side::Right, max.x - center.x - half.x, Vector::new(1., 0.)),
+                (
+                    Side::Bottom,
+                    center.y - half.y - min.y,
+                    Vector::new(0., -1.),
+                ),
+                (Side::Top, max.y - center.y - half.y, Vector::new(0., 1.)),
+            ]
+            .into_iter()
+            .filter(|(side, _, _)| monitor.is_open(*side, &converter.monitors))
+            .map(|(_, gap, direction)| (gap.max(0.), direction))
+            .filter(|(gap, _)| *gap < magnetism.capture_distance)
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        });
+
+        if let Some((gap, direction)) = pull {
+            forces.force +=
+                direction * magnetism.strength * (1. - gap / magnetism.capture_distance);
+            activation.wake_up(true);
+        }
+    }
+}
+
+/// Keeps a bouncing window circling the cursor, `radius` physics units away at `angular_speed`
+/// radians per second, anticlockwise when positive. The window is steered onto the orbit rather
+/// than put there, closing the gap to the orbit's speed and radius at `responsiveness` per second,
+/// and bounces freely again once this is switched off. Off by default.
+///
+/// The cursor is only known while it's over one of the windows, so the window goes on circling
+/// where it was last seen.
+pub struct OrbitConfig {
+    pub enabled: bool,
+    pub radius: Real,
+    pub angular_speed: Real,
+    pub responsiveness: Real,
+}
+
+impl Default for OrbitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: 0.3,
+            angular_speed: 1.,
+            responsiveness: 4.,
+        }
+    }
+}
+
+// gravity is cancelled out as well, so the orbit stays round
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn window_orbits_cursor(
+    orbit: Res<OrbitConfig>,
+    rapier_config: Res<RapierConfiguration>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+    mut target: Local<Option<Point<Real>>>,
+    mut window_query: Query<(
+        &PhysicsWindow,
+        &WindowState,
+        &RigidBodyPositionComponent,
+        &RigidBodyVelocityComponent,
+        &RigidBodyMassPropsComponent,
+        &mut RigidBodyForcesComponent,
+        &mut RigidBodyActivationComponent,
+    )>,
+) {
+    if !orbit.enabled {
+        return;
+    }
+    if let Some(cursor) = window_query.iter().find_map(|(physics_window, ..)| {
+        cursor_to_physics(&windows, &winit_windows, physics_window.id, &converter)
+    }) {
+        *target = Some(cursor);
+    }
+    let target = match *target {
+        Some(target) => target,
+        None => return,
+    };
+
+    for (_, window_state, position, velocity, rbmp, mut forces, mut activation) in
+        window_query.iter_mut()
+    {
+        if !matches!(window_state, WindowState::Bouncing) {
+            continue;
+        }
+        let offset = Point::from(position.position.translation.vector) - target;
+        let distance = offset.norm();
+        let outwards = if distance > Real::EPSILON {
+            offset / distance
+        } else {
+            Vector::x()
+        };
+        let along = Vector::new(-outwards.y, outwards.x);
+        let wanted = along * orbit.angular_speed * orbit.radius
+            + outwards * (orbit.radius - distance) * orbit.responsiveness;
+        let acceleration =
+            (wanted - velocity.linvel) * orbit.responsiveness - rapier_config.gravity;
+        forces.force += acceleration * rbmp.local_mprops.inv_mass.recip();
+        activation.wake_up(true);
+    }
+}
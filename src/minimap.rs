@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use winit::dpi::LogicalPosition;
+
+use crate::{
+    coords::{CoordConverter, MonitorRect},
+    PrimaryWindow,
+};
+
+// pixels across the minimap, its height follows the desktop's shape
+const MINIMAP_WIDTH: f32 = 160.;
+const DOT_SIZE: f32 = 6.;
+
+/// The minimap of the desktop in the bottom left corner, toggled with F4.
+#[derive(Component)]
+pub(crate) struct Minimap;
+
+/// Holds the monitor rectangles, under the dot.
+#[derive(Component)]
+pub(crate) struct MinimapMonitors;
+
+#[derive(Component)]
+pub(crate) struct MinimapDot;
+
+// top left of the box around all monitors in desktop units, minimap pixels per desktop unit, and
+// the minimap's height in pixels
+fn desktop_box(converter: &CoordConverter) -> (LogicalPosition<Real>, f32, f32) {
+    let monitors = &converter.monitors;
+    let left = monitors
+        .iter()
+        .map(MonitorRect::left)
+        .fold(Real::INFINITY, Real::min);
+    let top = monitors
+        .iter()
+        .map(MonitorRect::top)
+        .fold(Real::INFINITY, Real::min);
+    let right = monitors
+        .iter()
+        .map(MonitorRect::right)
+        .fold(Real::NEG_INFINITY, Real::max);
+    let bottom = monitors
+        .iter()
+        .map(MonitorRect::bottom)
+        .fold(Real::NEG_INFINITY, Real::max);
+    let scale = MINIMAP_WIDTH / (right - left).max(1.);
+    (
+        LogicalPosition::new(left, top),
+        scale,
+        (bottom - top) * scale,
+    )
+}
+
+fn absolute(left: f32, top: f32, width: f32, height: f32) -> Style {
+    Style {
+        position_type: PositionType::Absolute,
+        position: Rect {
+            left: Val::Px(left),
+            top: Val::Px(top),
+            ..Default::default()
+        },
+        size: Size::new(Val::Px(width), Val::Px(height)),
+        ..Default::default()
+    }
+}
+
+// anchored to the corner, so it stays put however the window is resized
+pub(crate) fn spawn_minimap(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(5.),
+                    left: Val::Px(5.),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Px(MINIMAP_WIDTH), Val::Px(MINIMAP_WIDTH)),
+                ..Default::default()
+            },
+            color: Color::rgba(0., 0., 0., 0.5).into(),
+            ..Default::default()
+        })
+        .insert(Minimap)
+        .with_children(|minimap| {
+            // later siblings are drawn on top
+            minimap
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .insert(MinimapMonitors);
+            minimap
+                .spawn_bundle(NodeBundle {
+                    style: absolute(0., 0., DOT_SIZE, DOT_SIZE),
+                    color: Color::WHITE.into(),
+                    ..Default::default()
+                })
+                .insert(MinimapDot);
+        });
+}
+
+pub(crate) fn f4_toggles_minimap(
+    keys: Res<Input<KeyCode>>,
+    mut minimap: Query<&mut Style, With<Minimap>>,
+) {
+    if keys.just_pressed(KeyCode::F4) {
+        for mut style in minimap.iter_mut() {
+            style.display = match style.display {
+                Display::None => Display::Flex,
+                Display::Flex => Display::None,
+            };
+        }
+    }
+}
+
+// redrawn from scratch whenever the monitors are measured again
+pub(crate) fn minimap_shows_monitors(
+    mut commands: Commands,
+    converter: Res<CoordConverter>,
+    mut minimap: Query<&mut Style, With<Minimap>>,
+    layer: Query<(Entity, Option<&Children>), With<MinimapMonitors>>,
+) {
+    if !converter.is_changed() {
+        return;
+    }
+    let (origin, scale, height) = desktop_box(&converter);
+    for mut style in minimap.iter_mut() {
+        style.size.height = Val::Px(height);
+    }
+    for (layer, children) in layer.iter() {
+        for &child in children.into_iter().flat_map(|children| children.iter()) {
+            commands.entity(child).despawn_recursive();
+        }
+        for monitor in &converter.monitors {
+            let rect = commands
+                .spawn_bundle(NodeBundle {
+                    // a pixel short, so neighbouring monitors are told apart
+                    style: absolute(
+                        (monitor.left() - origin.x) * scale,
+                        (monitor.top() - origin.y) * scale,
+                        (monitor.size.width * scale - 1.).max(1.),
+                        (monitor.size.height * scale - 1.).max(1.),
+                    ),
+                    color: Color::DARK_GRAY.into(),
+                    ..Default::default()
+                })
+                .id();
+            commands.entity(layer).add_child(rect);
+        }
+    }
+}
+
+pub(crate) fn minimap_follows_window(
+    converter: Res<CoordConverter>,
+    window: Query<&RigidBodyPositionComponent, With<PrimaryWindow>>,
+    mut dot: Query<&mut Style, With<MinimapDot>>,
+) {
+    let position = match window.get_single() {
+        Ok(position) => position,
+        Err(e) => {
+            debug!("No primary window to show on the minimap: {}", e);
+            return;
+        }
+    };
+    let (origin, scale, _) = desktop_box(&converter);
+    let center = converter.to_logical_winit_position(position.position.translation.vector.into());
+    for mut style in dot.iter_mut() {
+        style.position.left = Val::Px((center.x - origin.x) * scale - DOT_SIZE / 2.);
+        style.position.top = Val::Px((center.y - origin.y) * scale - DOT_SIZE / 2.);
+    }
+}
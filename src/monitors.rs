@@ -0,0 +1,110 @@
+use bevy::{prelude::*, winit::WinitWindows};
+use bevy_rapier2d::prelude::*;
+use winit::dpi::LogicalPosition;
+
+use crate::{
+    coords::{monitor_collider, CoordConverter},
+    simulation::SimulationPaused,
+    BoundaryMode, MonitorBounds,
+};
+
+// seconds between measuring the monitors again, winit has no event for them changing
+const MONITOR_POLL_INTERVAL: f64 = 1.;
+
+pub(crate) fn spawn_monitor_bounds(
+    commands: &mut Commands,
+    converter: &CoordConverter,
+    boundary_mode: BoundaryMode,
+) {
+    for monitor in &converter.monitors {
+        let shape = match monitor_collider(monitor, &converter.monitors, converter) {
+            Some(shape) => shape,
+            None => continue,
+        };
+        commands
+            .spawn()
+            .insert_bundle(ColliderBundle {
+                shape: shape.into(),
+                position: Isometry::new(converter.to_physics_point(monitor.center()).coords, 0.)
+                    .into(),
+                material: ColliderMaterial::new(0.8, 0.3).into(),
+                flags: ColliderFlags {
+                    collision_groups: boundary_mode.monitor_groups(),
+                    ..Default::default()
+                }
+                .into(),
+                ..Default::default()
+            })
+            .insert(MonitorBounds);
+    }
+}
+
+// after a resolution change or a monitor being plugged in or out, the desktop bounds are rebuilt
+// and every body is moved so it keeps its place on the desktop. While the primary window is on no
+// monitor at all the simulation is paused
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn monitor_changes_refresh_bounds(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut last_poll: Local<f64>,
+    mut paused_for_monitor: Local<bool>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    mut converter: ResMut<CoordConverter>,
+    boundary_mode: Res<BoundaryMode>,
+    mut paused: ResMut<SimulationPaused>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    bounds: Query<Entity, With<MonitorBounds>>,
+    mut bodies: Query<&mut RigidBodyPositionComponent>,
+) {
+    let now = time.seconds_since_startup();
+    if now - *last_poll < MONITOR_POLL_INTERVAL {
+        return;
+    }
+    *last_poll = now;
+
+    let window = match windows
+        .get_primary()
+        .and_then(|w| winit_windows.get_window(w.id()))
+    {
+        Some(window) => window,
+        None => return,
+    };
+    // the desktop scale is kept, moving onto a monitor of another scale factor mustn't resize the
+    // physics world under the bodies
+    let refreshed = match converter.remeasure(window) {
+        Some(refreshed) => refreshed,
+        None => {
+            if !*paused_for_monitor && !paused.0 {
+                warn!("Primary window is on no monitor, pausing until it is");
+                paused.0 = true;
+                rapier_config.physics_pipeline_active = false;
+                *paused_for_monitor = true;
+            }
+            return;
+        }
+    };
+    if *paused_for_monitor {
+        paused.0 = false;
+        rapier_config.physics_pipeline_active = true;
+        *paused_for_monitor = false;
+    }
+    if refreshed.monitors == converter.monitors {
+        return;
+    }
+    info!("Monitors changed, rebuilding the desktop bounds");
+
+    // physics is measured from the bottom of the desktop, which may have moved
+    let origin = LogicalPosition::new(0., 0.);
+    let shift = refreshed.to_physics_point(origin) - converter.to_physics_point(origin);
+    for mut position in bodies.iter_mut() {
+        position.position.translation.vector += shift;
+        position.next_position.translation.vector += shift;
+    }
+
+    for entity in bounds.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_monitor_bounds(&mut commands, &refreshed, *boundary_mode);
+    *converter = refreshed;
+}
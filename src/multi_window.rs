@@ -0,0 +1,176 @@
+use bevy::{
+    core_pipeline::{draw_2d_graph, node, Transparent2d},
+    prelude::*,
+    render::{
+        camera::{ActiveCameras, ExtractedCameraNames},
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotValue},
+        render_phase::RenderPhase,
+        renderer::RenderContext,
+        RenderApp, RenderStage,
+    },
+    window::{CreateWindow, WindowCreated, WindowId},
+    winit::WinitWindows,
+};
+use bevy_rapier2d::prelude::*;
+use winit::dpi::LogicalSize;
+
+use crate::{
+    coords::CoordConverter,
+    fallback_center, groups,
+    shapes::{JellyConfig, ShapeRng, ShapeWeights, SpawnConfig},
+    spawn_window_body, window_center, PhysicsWindow, WindowDensity, WindowFrame,
+};
+
+/// The windows opened on top of the primary one, in the order their interior groups were handed
+/// out.
+#[derive(Default)]
+pub(crate) struct ExtraWindows(Vec<WindowId>);
+
+// bevy only draws the 2d camera named CAMERA_2D, so each extra window's camera gets a name of its
+// own and is drawn by ExtraWindowsDriver
+fn camera_name(index: usize) -> String {
+    format!("window_physics_{}", index)
+}
+
+pub(crate) fn open_extra_windows(
+    commands: &mut Commands,
+    create_window_events: &mut EventWriter<CreateWindow>,
+    count: usize,
+    size: LogicalSize<f32>,
+) {
+    let ids = (1..count)
+        .map(|_| {
+            let id = WindowId::new();
+            create_window_events.send(CreateWindow {
+                id,
+                descriptor: WindowDescriptor {
+                    title: "window.velocity".to_string(),
+                    width: size.width,
+                    height: size.height,
+                    transparent: true,
+                    ..Default::default()
+                },
+            });
+            id
+        })
+        .collect();
+    commands.insert_resource(ExtraWindows(ids));
+}
+
+// winit only opens the windows after startup, so their bodies are spawned once they exist
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn spawn_extra_window_bodies(
+    mut commands: Commands,
+    mut created_events: EventReader<WindowCreated>,
+    extra_windows: Res<ExtraWindows>,
+    winit_windows: Res<WinitWindows>,
+    mut active_cameras: ResMut<ActiveCameras>,
+    converter: Res<CoordConverter>,
+    window_density: Res<WindowDensity>,
+    spawn_config: Res<SpawnConfig>,
+    weights: Res<ShapeWeights>,
+    jelly: Res<JellyConfig>,
+    mut rng: ResMut<ShapeRng>,
+) {
+    for event in created_events.iter() {
+        let index = match extra_windows.0.iter().position(|id| *id == event.id) {
+            Some(i) => i + 1,
+            None => continue,
+        };
+        let window = match winit_windows.get_window(event.id) {
+            Some(window) => window,
+            None => continue,
+        };
+
+        let name = camera_name(index);
+        let mut camera = OrthographicCameraBundle::new_2d();
+        camera.camera.window = event.id;
+        camera.camera.name = Some(name.clone());
+        active_cameras.add(&name);
+
+        spawn_window_body(
+            &mut commands,
+            &WindowFrame::of(window, &converter),
+            PhysicsWindow {
+                id: event.id,
+                interior: groups::interior(index),
+            },
+            camera,
+            &converter,
+            &window_density,
+            &spawn_config,
+            &weights,
+            &jelly,
+            &mut rng.0,
+            window_center(window, &converter).unwrap_or_else(|| fallback_center(&converter)),
+            Vector::zeros(),
+        );
+    }
+}
+
+// names of the extra window cameras that made it into the render world this frame
+struct ExtraWindowCameras(Vec<String>);
+
+fn extract_extra_window_cameras(
+    mut commands: Commands,
+    extra_windows: Res<ExtraWindows>,
+    active_cameras: Res<ActiveCameras>,
+) {
+    let mut names = Vec::new();
+    for index in 1..=extra_windows.0.len() {
+        let name = camera_name(index);
+        if let Some(entity) = active_cameras.get(&name).and_then(|camera| camera.entity) {
+            commands
+                .get_or_spawn(entity)
+                .insert(RenderPhase::<Transparent2d>::default());
+            names.push(name);
+        }
+    }
+    commands.insert_resource(ExtraWindowCameras(names));
+}
+
+const EXTRA_WINDOWS_DRIVER: &str = "window_physics_extra_windows";
+
+// runs the 2d pass once for each extra window's camera
+struct ExtraWindowsDriver;
+
+impl Node for ExtraWindowsDriver {
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        _render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let extracted = world.get_resource::<ExtractedCameraNames>().unwrap();
+        let cameras = match world.get_resource::<ExtraWindowCameras>() {
+            Some(cameras) => cameras,
+            None => return Ok(()),
+        };
+        for name in &cameras.0 {
+            if let Some(&camera) = extracted.entities.get(name) {
+                graph.run_sub_graph(draw_2d_graph::NAME, vec![SlotValue::Entity(camera)])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Draws the extra windows. Without a renderer, e.g. when headless, only the resource is added.
+pub(crate) struct MultiWindowRenderPlugin;
+
+impl Plugin for MultiWindowRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExtraWindows>();
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app.add_system_to_stage(RenderStage::Extract, extract_extra_window_cameras);
+        let mut graph = render_app.world.get_resource_mut::<RenderGraph>().unwrap();
+        graph.add_node(EXTRA_WINDOWS_DRIVER, ExtraWindowsDriver);
+        graph
+            .add_node_edge(node::MAIN_PASS_DEPENDENCIES, EXTRA_WINDOWS_DRIVER)
+            .unwrap();
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt::Write;
+
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    coords::CoordConverter, shapes::InteriorShape, stats::CollisionStats, PrimaryWindow,
+    WindowState,
+};
+
+#[derive(Component)]
+pub(crate) struct DiagnosticsText;
+
+pub(crate) fn spawn_diagnostics_overlay(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn_bundle(UiCameraBundle::default());
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(5.),
+                    left: Val::Px(5.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/DejaVuSansMono.ttf"),
+                    font_size: 16.,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(DiagnosticsText);
+}
+
+pub(crate) fn f3_toggles_diagnostics(
+    keys: Res<Input<KeyCode>>,
+    mut overlay: Query<&mut Visibility, With<DiagnosticsText>>,
+) {
+    if keys.just_pressed(KeyCode::F3) {
+        for mut visibility in overlay.iter_mut() {
+            visibility.is_visible = !visibility.is_visible;
+        }
+    }
+}
+
+// the text is rewritten in place, so its string keeps its allocation from frame to frame
+pub(crate) fn update_diagnostics(
+    diagnostics: Res<Diagnostics>,
+    window_query: Query<(&WindowState, &RigidBodyVelocityComponent), With<PrimaryWindow>>,
+    shapes: Query<Entity, With<InteriorShape>>,
+    activations: Query<&RigidBodyActivationComponent, Or<(With<WindowState>, With<InteriorShape>)>>,
+    converter: Res<CoordConverter>,
+    collision_stats: Res<CollisionStats>,
+    mut overlay: Query<(&mut Text, &Visibility), With<DiagnosticsText>>,
+) {
+    let (window_state, velocity) = match window_query.get_single() {
+        Ok(window) => window,
+        Err(e) => {
+            debug!("No primary window to show diagnostics for: {}", e);
+            return;
+        }
+    };
+    for (mut text, visibility) in overlay.iter_mut() {
+        if !visibility.is_visible {
+            continue;
+        }
+        let value = &mut text.sections[0].value;
+        value.clear();
+
+        let state = match window_state {
+            WindowState::Bouncing => "bouncing",
+            WindowState::Dragging(_) => "dragging",
+            WindowState::Static => "static",
+        };
+        let _ = writeln!(value, "state: {}", state);
+        let _ = writeln!(
+            value,
+            "speed: {:.0} px/s",
+            velocity.linvel.norm() * converter.physics_scale
+        );
+        let _ = writeln!(value, "shapes: {}", shapes.iter().count());
+        let _ = writeln!(
+            value,
+            "asleep: {} of {} bodies",
+            activations.iter().filter(|a| a.sleeping).count(),
+            activations.iter().count()
+        );
+        let _ = writeln!(
+            value,
+            "collisions: {} ({} edge, {} shape)",
+            collision_stats.total, collision_stats.window_edge, collision_stats.shape_shape
+        );
+        match diagnostics
+            .get(FrameTimeDiagnosticsPlugin::FPS)
+            .and_then(|fps| fps.average())
+        {
+            Some(fps) => {
+                let _ = write!(value, "fps: {:.0}", fps);
+            }
+            None => value.push_str("fps: -"),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use bevy::{app::AppExit, prelude::*};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{PrimaryWindow, WindowState};
+
+/// State of the primary window, written next to the executable on exit and restored on the next
+/// launch.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SavedWindow {
+    pub(crate) state: WindowState,
+    pub(crate) center: [Real; 2], // physics units
+    pub(crate) linvel: [Real; 2],
+}
+
+impl SavedWindow {
+    fn path() -> std::io::Result<PathBuf> {
+        Ok(std::env::current_exe()?.with_file_name("window-state.ron"))
+    }
+
+    pub(crate) fn load() -> Option<Self> {
+        let path = Self::path().ok()?;
+        let contents = std::fs::read_to_string(&path).ok()?;
+        match ron::from_str(&contents) {
+            Ok(saved) => Some(saved),
+            Err(e) => {
+                warn!("Ignoring unreadable {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let contents = ron::ser::to_string_pretty(self, Default::default())?;
+        std::fs::write(Self::path()?, contents)?;
+        Ok(())
+    }
+}
+
+pub(crate) fn save_window_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    window_query: Query<
+        (
+            &WindowState,
+            &RigidBodyPositionComponent,
+            &RigidBodyVelocityComponent,
+        ),
+        With<PrimaryWindow>,
+    >,
+) {
+    if exit_events.iter().next().is_none() {
+        return;
+    }
+    if let Ok((state, position, velocity)) = window_query.get_single() {
+        let saved = SavedWindow {
+            state: *state,
+            center: position.position.translation.vector.into(),
+            linvel: velocity.linvel.into(),
+        };
+        if let Err(e) = saved.save() {
+            warn!("Failed to save window state: {}", e);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+
+use bevy::{prelude::*, winit::WinitWindows};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    center_offset, coords::CoordConverter, simulation::SimulationPaused, PhysicsWindow,
+    PrimaryWindow, WindowState,
+};
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct Sample {
+    time: f64,         // seconds since the recording started
+    center: [Real; 2], // physics units
+    state: WindowState,
+}
+
+enum Mode {
+    Idle,
+    Recording { start: f64 },
+    Playing { start: f64, next: usize },
+}
+
+/// The primary window's motion, recorded with F5, played back with F6 and saved with F7 to
+/// `window-recording.ron` next to the executable.
+pub struct Recorder {
+    mode: Mode,
+    samples: Vec<Sample>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Idle,
+            samples: Vec::new(),
+        }
+    }
+}
+
+impl Recorder {
+    pub fn is_recording(&self) -> bool {
+        matches!(self.mode, Mode::Recording { .. })
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self.mode, Mode::Playing { .. })
+    }
+
+    fn path() -> std::io::Result<PathBuf> {
+        Ok(std::env::current_exe()?.with_file_name("window-recording.ron"))
+    }
+
+    fn save(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        let contents = ron::ser::to_string_pretty(&self.samples, Default::default())?;
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
+
+// physics is stopped during playback so it doesn't fight the recorded positions, and started
+// again afterwards unless the simulation is paused
+pub(crate) fn function_keys_control_recorder(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut recorder: ResMut<Recorder>,
+    paused: Res<SimulationPaused>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    let now = time.seconds_since_startup();
+    if keys.just_pressed(KeyCode::F5) {
+        recorder.mode = if recorder.is_recording() {
+            info!("Recorded {} samples", recorder.samples.len());
+            Mode::Idle
+        } else {
+            recorder.samples.clear();
+            Mode::Recording { start: now }
+        };
+    }
+    if keys.just_pressed(KeyCode::F6) {
+        recorder.mode = if recorder.is_playing() || recorder.samples.is_empty() {
+            Mode::Idle
+        } else {
+            Mode::Playing {
+                start: now,
+                next: 0,
+            }
+        };
+    }
+    if keys.any_just_pressed([KeyCode::F5, KeyCode::F6]) {
+        rapier_config.physics_pipeline_active = !recorder.is_playing() && !paused.0;
+    }
+    if keys.just_pressed(KeyCode::F7) {
+        match recorder.save() {
+            Ok(path) => info!("Saved the recording to {}", path.display()),
+            Err(e) => warn!("Failed to save the recording: {}", e),
+        }
+    }
+}
+
+pub(crate) fn record_window(
+    time: Res<Time>,
+    mut recorder: ResMut<Recorder>,
+    window_query: Query<(&WindowState, &RigidBodyPositionComponent), With<PrimaryWindow>>,
+) {
+    let start = match recorder.mode {
+        Mode::Recording { start } => start,
+        _ => return,
+    };
+    if let Ok((state, position)) = window_query.get_single() {
+        recorder.samples.push(Sample {
+            time: time.seconds_since_startup() - start,
+            center: position.position.translation.vector.into(),
+            state: *state,
+        });
+    }
+}
+
+// the OS window is moved as well as the body, so the sync for static windows, which reads the OS
+// window's position, agrees with it
+pub(crate) fn replay_window(
+    time: Res<Time>,
+    mut recorder: ResMut<Recorder>,
+    paused: Res<SimulationPaused>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut window_query: Query<
+        (
+            &PhysicsWindow,
+            &mut WindowState,
+            &mut RigidBodyPositionComponent,
+        ),
+        With<PrimaryWindow>,
+    >,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+) {
+    let (start, next) = match recorder.mode {
+        Mode::Playing { start, next } => (start, next),
+        _ => return,
+    };
+    let elapsed = time.seconds_since_startup() - start;
+    let next = next
+        + recorder.samples[next..]
+            .iter()
+            .take_while(|sample| sample.time <= elapsed)
+            .count();
+    if next >= recorder.samples.len() {
+        recorder.mode = Mode::Idle;
+        rapier_config.physics_pipeline_active = !paused.0;
+        return;
+    }
+    recorder.mode = Mode::Playing { start, next };
+    let sample = recorder.samples[next.saturating_sub(1)];
+
+    let (physics_window, mut state, mut position) = match window_query.get_single_mut() {
+        Ok(window) => window,
+        Err(e) => {
+            debug!("No primary window to replay: {}", e);
+            return;
+        }
+    };
+    // written only when it changes, so reactions to a state change happen once
+    if std::mem::discriminant(&*state) != std::mem::discriminant(&sample.state) {
+        *state = sample.state;
+    }
+    let center = Point::from(sample.center);
+    position.position = Isometry::new(center.coords, 0.);
+    position.next_position = position.position;
+    if let Some(window) = winit_windows.get_window(physics_window.id) {
+        let top_left = center - center_offset(window, &converter);
+        window.set_outer_position(
+            converter.to_physical(converter.to_logical_winit_position(top_left)),
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::path::PathBuf;
+
+use bevy::{prelude::*, winit::WinitWindows};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    center_offset,
+    coords::CoordConverter,
+    shapes::{spawn_shape, InteriorShape, Lifetime, ShapeKind, ShapeRng, ShapeSpec},
+    PhysicsWindow, SpawnConfig, WindowState,
+};
+
+// bumped whenever SceneSnapshot changes, older files are refused rather than misread
+const SCENE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct WindowSnapshot {
+    interior: u32, // the window's collision group, which stays the same between runs
+    state: WindowState,
+    center: [Real; 2], // physics units
+    linvel: [Real; 2],
+}
+
+#[derive(Serialize, Deserialize)]
+struct ShapeSnapshot {
+    window: u32, // the interior group of the window it's in
+    kind: ShapeKind,
+    size: Real,
+    colour: [f32; 4], // sRGBA
+    position: [Real; 2],
+    angle: Real,
+    linvel: [Real; 2],
+    angvel: Real,
+}
+
+/// Every window and the shapes in it, saved with F8 to `scene.ron` next to the executable and
+/// loaded back with F9. Short-lived confetti and sparks are left out, and jelly comes back as loose
+/// circles.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SceneSnapshot {
+    version: u32,
+    windows: Vec<WindowSnapshot>,
+    shapes: Vec<ShapeSnapshot>,
+}
+
+impl SceneSnapshot {
+    fn path() -> std::io::Result<PathBuf> {
+        Ok(std::env::current_exe()?.with_file_name("scene.ron"))
+    }
+
+    fn save(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = Self::path()?;
+        let contents = ron::ser::to_string_pretty(self, Default::default())?;
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let contents = std::fs::read_to_string(Self::path()?)?;
+        let snapshot: Self = ron::from_str(&contents)?;
+        if snapshot.version != SCENE_VERSION {
+            return Err(format!(
+                "it was saved as version {}, this build reads version {}",
+                snapshot.version, SCENE_VERSION
+            )
+            .into());
+        }
+        Ok(snapshot)
+    }
+}
+
+pub(crate) fn save_scene(
+    keys: Res<Input<KeyCode>>,
+    window_query: Query<(
+        &PhysicsWindow,
+        &WindowState,
+        &RigidBodyPositionComponent,
+        &RigidBodyVelocityComponent,
+    )>,
+    shapes: Query<
+        (
+            &ShapeSpec,
+            &InteriorShape,
+            &RigidBodyPositionComponent,
+            &RigidBodyVelocityComponent,
+        ),
+        Without<Lifetime>,
+    >,
+) {
+    if !keys.just_pressed(KeyCode::F8) {
+        return;
+    }
+    let snapshot = SceneSnapshot {
+        version: SCENE_VERSION,
+        windows: window_query
+            .iter()
+            .map(
+                |(physics_window, state, position, velocity)| WindowSnapshot {
+                    interior: physics_window.interior,
+                    state: *state,
+                    center: position.position.translation.vector.into(),
+                    linvel: velocity.linvel.into(),
+                },
+            )
+            .collect(),
+        shapes: shapes
+            .iter()
+            .filter_map(|(spec, shape, position, velocity)| {
+                let (physics_window, _, _, _) = window_query.get(shape.window).ok()?;
+                Some(ShapeSnapshot {
+                    window: physics_window.interior,
+                    kind: spec.kind,
+                    size: spec.size,
+                    colour: spec.colour.as_rgba_f32(),
+                    position: position.position.translation.vector.into(),
+                    angle: position.position.rotation.angle(),
+                    linvel: velocity.linvel.into(),
+                    angvel: velocity.angvel,
+                })
+            })
+            .collect(),
+    };
+    match snapshot.save() {
+        Ok(path) => info!("Saved the scene to {}", path.display()),
+        Err(e) => warn!("Failed to save the scene: {}", e),
+    }
+}
+
+// the OS windows are moved as well as the bodies, since static windows follow their OS window
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn load_scene(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+    spawn_config: Res<SpawnConfig>,
+    mut rng: ResMut<ShapeRng>,
+    shapes: Query<Entity, With<InteriorShape>>,
+    mut window_query: Query<(
+        Entity,
+        &PhysicsWindow,
+        &mut WindowState,
+        &mut RigidBodyPositionComponent,
+        &mut RigidBodyVelocityComponent,
+    )>,
+) {
+    if !keys.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let snapshot = match SceneSnapshot::load() {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            warn!("Not loading the scene: {}", e);
+            return;
+        }
+    };
+
+    for shape in shapes.iter() {
+        commands.entity(shape).despawn_recursive();
+    }
+
+    let mut bodies = Vec::new();
+    for (entity, physics_window, mut state, mut position, mut velocity) in window_query.iter_mut() {
+        bodies.push((physics_window.interior, entity));
+        let saved = match snapshot
+            .windows
+            .iter()
+            .find(|saved| saved.interior == physics_window.interior)
+        {
+            Some(saved) => saved,
+            None => continue,
+        };
+        *state = saved.state;
+        position.position = Isometry::new(saved.center.into(), 0.);
+        position.next_position = position.position;
+        velocity.linvel = saved.linvel.into();
+        velocity.angvel = 0.;
+        if let Some(window) = winit_windows.get_window(physics_window.id) {
+            let top_left = Point::from(saved.center) - center_offset(window, &converter);
+            window.set_outer_position(
+                converter.to_physical(converter.to_logical_winit_position(top_left)),
+            );
+        }
+    }
+
+    for saved in &snapshot.shapes {
+        let window = match bodies
+            .iter()
+            .find(|(interior, _)| *interior == saved.window)
+        {
+            Some(&(interior, body)) => (body, interior),
+            None => continue,
+        };
+        let [r, g, b, a] = saved.colour;
+        let spec = ShapeSpec {
+            kind: saved.kind,
+            size: saved.size,
+            colour: Color::rgba(r, g, b, a),
+        };
+        let position = Isometry::new(saved.position.into(), saved.angle);
+        let shape = spawn_shape(
+            &mut commands,
+            &converter,
+            &spawn_config,
+            &mut rng.0,
+            window,
+            position.translation.vector.into(),
+            spec,
+        );
+        commands
+            .entity(shape)
+            .insert(RigidBodyPositionComponent::from(RigidBodyPosition {
+                position,
+                next_position: position,
+            }))
+            .insert(RigidBodyVelocityComponent::from(RigidBodyVelocity {
+                linvel: saved.linvel.into(),
+                angvel: saved.angvel,
+            }));
+    }
+    info!("Loaded {} shapes", snapshot.shapes.len());
+}
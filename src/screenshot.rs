@@ -0,0 +1,319 @@
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    core_pipeline::{node, Transparent2d},
+    prelude::*,
+    render::{
+        camera::{CameraPlugin, ExtractedCameraNames},
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext},
+        render_phase::{DrawFunctions, RenderPhase, TrackedRenderPass},
+        render_resource::{
+            Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout,
+            LoadOp, MapMode, Operations, RenderPassColorAttachment, RenderPassDescriptor, Texture,
+            TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+            TextureViewDescriptor,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::{ExtractedWindows, Msaa},
+        RenderApp, RenderStage,
+    },
+    window::WindowId,
+    winit::WinitWindows,
+};
+
+/// Where F12 saves a PNG of the primary window. Relative paths are from the working directory.
+///
+/// The file is named after when it was taken and where the window's top left corner was on the
+/// desktop, in physical pixels, e.g. `screenshot-1700000000123-at-640_360.png`, so it can be put
+/// back over a screenshot of the desktop. Only the window's 2D camera is drawn again for it, so
+/// the shapes and the window's background are in the PNG but the UI, such as the F3 overlay,
+/// isn't. Neither is the desktop behind a transparent window.
+pub struct ScreenshotConfig {
+    pub dir: PathBuf,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("."),
+        }
+    }
+}
+
+/// The file a screenshot was asked for this frame, taken by the renderer.
+#[derive(Default)]
+pub(crate) struct ScreenshotRequest(Option<PathBuf>);
+
+pub(crate) fn f12_takes_screenshot(
+    keys: Res<Input<KeyCode>>,
+    config: Res<ScreenshotConfig>,
+    winit_windows: Res<WinitWindows>,
+    mut request: ResMut<ScreenshotRequest>,
+) {
+    if !keys.just_pressed(KeyCode::F12) {
+        if request.0.is_some() {
+            request.0 = None;
+        }
+        return;
+    }
+    let window = match winit_windows.get_window(WindowId::primary()) {
+        Some(window) => window,
+        None => return,
+    };
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    let name = match window.outer_position() {
+        Ok(p) => format!("screenshot-{}-at-{}_{}.png", millis, p.x, p.y),
+        // Wayland doesn't tell windows where they are
+        Err(_) => format!("screenshot-{}.png", millis),
+    };
+    request.0 = Some(config.dir.join(name));
+}
+
+// in the render world from here on
+
+struct ScreenshotPath(PathBuf);
+
+fn extract_screenshot_request(mut commands: Commands, request: Res<ScreenshotRequest>) {
+    if let Some(path) = &request.0 {
+        commands.insert_resource(ScreenshotPath(path.clone()));
+    }
+}
+
+// rows copied out of a texture have to start on this many bytes
+const COPY_ROW_ALIGNMENT: u32 = 256;
+
+// the swapchain can't be copied from, so the primary camera's 2d phase is drawn again into a
+// texture of our own, resolved from a multisampled one like the window's
+struct PendingScreenshot {
+    path: PathBuf,
+    size: Extent3d,
+    padded_row: u32,
+    multisampled: Option<TextureView>,
+    resolved: Texture,
+    resolved_view: TextureView,
+    buffer: Buffer,
+    copied: AtomicBool,
+}
+
+fn prepare_screenshot(
+    mut commands: Commands,
+    path: Option<Res<ScreenshotPath>>,
+    windows: Res<ExtractedWindows>,
+    msaa: Res<Msaa>,
+    render_device: Res<RenderDevice>,
+) {
+    let path = match path {
+        Some(path) => path.0.clone(),
+        None => return,
+    };
+    commands.remove_resource::<ScreenshotPath>();
+    let window = match windows.windows.get(&WindowId::primary()) {
+        Some(window) if window.physical_width > 0 && window.physical_height > 0 => window,
+        _ => {
+            warn!("No primary window to take a screenshot of");
+            return;
+        }
+    };
+
+    let size = Extent3d {
+        width: window.physical_width,
+        height: window.physical_height,
+        depth_or_array_layers: 1,
+    };
+    let texture = |sample_count, usage| {
+        render_device.create_texture(&TextureDescriptor {
+            label: Some("screenshot"),
+            size,
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::bevy_default(),
+            usage,
+        })
+    };
+    let resolved = texture(
+        1,
+        TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+    );
+    let multisampled = (msaa.samples > 1).then(|| {
+        texture(msaa.samples, TextureUsages::RENDER_ATTACHMENT)
+            .create_view(&TextureViewDescriptor::default())
+    });
+    let padded_row =
+        (size.width * 4 + COPY_ROW_ALIGNMENT - 1) / COPY_ROW_ALIGNMENT * COPY_ROW_ALIGNMENT;
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("screenshot"),
+        size: (padded_row * size.height) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    commands.insert_resource(PendingScreenshot {
+        path,
+        size,
+        padded_row,
+        multisampled,
+        resolved_view: resolved.create_view(&TextureViewDescriptor::default()),
+        resolved,
+        buffer,
+        copied: AtomicBool::new(false),
+    });
+}
+
+const SCREENSHOT_NODE: &str = "window_physics_screenshot";
+
+struct ScreenshotNode;
+
+impl Node for ScreenshotNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let screenshot = match world.get_resource::<PendingScreenshot>() {
+            Some(screenshot) => screenshot,
+            None => return Ok(()),
+        };
+        let view = world
+            .get_resource::<ExtractedCameraNames>()
+            .and_then(|names| names.entities.get(CameraPlugin::CAMERA_2D).copied());
+        let (view, phase) = match view
+            .and_then(|view| Some((view, world.get::<RenderPhase<Transparent2d>>(view)?)))
+        {
+            Some(view) => view,
+            None => return Ok(()),
+        };
+        let clear_colour = world
+            .get_resource::<ClearColor>()
+            .map_or(Color::BLACK, |clear| clear.0);
+
+        {
+            let (view_texture, resolve_target) = match &screenshot.multisampled {
+                Some(multisampled) => (multisampled, Some(&*screenshot.resolved_view)),
+                None => (&screenshot.resolved_view, None),
+            };
+            let render_pass =
+                render_context
+                    .command_encoder
+                    .begin_render_pass(&RenderPassDescriptor {
+                        label: Some("screenshot"),
+                        color_attachments: &[RenderPassColorAttachment {
+                            view: view_texture,
+                            resolve_target,
+                            ops: Operations {
+                                load: LoadOp::Clear(clear_colour.into()),
+                                store: true,
+                            },
+                        }],
+                        depth_stencil_attachment: None,
+                    });
+            let draw_functions = world
+                .get_resource::<DrawFunctions<Transparent2d>>()
+                .unwrap();
+            let mut draw_functions = draw_functions.write();
+            let mut tracked_pass = TrackedRenderPass::new(render_pass);
+            for item in &phase.items {
+                if let Some(draw_function) = draw_functions.get_mut(item.draw_function) {
+                    draw_function.draw(world, &mut tracked_pass, view, item);
+                }
+            }
+        }
+
+        render_context.command_encoder.copy_texture_to_buffer(
+            screenshot.resolved.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &screenshot.buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(screenshot.padded_row),
+                    rows_per_image: None,
+                },
+            },
+            screenshot.size,
+        );
+        screenshot.copied.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+// the frame has been submitted by now. The file is written before this frame's update returns, so
+// it's on disk even if the app exits straight after
+fn save_screenshot(
+    mut commands: Commands,
+    screenshot: Option<Res<PendingScreenshot>>,
+    render_device: Res<RenderDevice>,
+) {
+    let screenshot = match screenshot {
+        Some(screenshot) => screenshot,
+        None => return,
+    };
+    commands.remove_resource::<PendingScreenshot>();
+    if !screenshot.copied.load(Ordering::Relaxed) {
+        warn!("The primary window wasn't drawn, no screenshot was taken");
+        return;
+    }
+
+    let slice = screenshot.buffer.slice(..);
+    render_device.map_buffer(&slice, MapMode::Read);
+    let Extent3d { width, height, .. } = screenshot.size;
+    let bgra = TextureFormat::bevy_default() == TextureFormat::Bgra8UnormSrgb;
+    let rgba: Vec<u8> = slice
+        .get_mapped_range()
+        .chunks(screenshot.padded_row as usize)
+        .flat_map(|row| row[..width as usize * 4].chunks(4))
+        .flat_map(|p| {
+            if bgra {
+                [p[2], p[1], p[0], p[3]]
+            } else {
+                [p[0], p[1], p[2], p[3]]
+            }
+        })
+        .collect();
+    screenshot.buffer.unmap();
+
+    match image::save_buffer(
+        &screenshot.path,
+        &rgba,
+        width,
+        height,
+        image::ColorType::Rgba8,
+    ) {
+        Ok(()) => info!("Saved a screenshot to {}", screenshot.path.display()),
+        Err(e) => warn!(
+            "Failed to save a screenshot to {}: {}",
+            screenshot.path.display(),
+            e
+        ),
+    }
+}
+
+/// Takes the screenshots asked for with F12. Without a renderer, e.g. when headless, nothing is
+/// added.
+pub(crate) struct ScreenshotRenderPlugin;
+
+impl Plugin for ScreenshotRenderPlugin {
+    fn build(&self, app: &mut App) {
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+        render_app
+            .add_system_to_stage(RenderStage::Extract, extract_screenshot_request)
+            .add_system_to_stage(RenderStage::Prepare, prepare_screenshot)
+            .add_system_to_stage(RenderStage::Cleanup, save_screenshot);
+        let mut graph = render_app.world.get_resource_mut::<RenderGraph>().unwrap();
+        graph.add_node(SCREENSHOT_NODE, ScreenshotNode);
+        // after the window itself is drawn, so the phase has everything in it
+        graph
+            .add_node_edge(node::MAIN_PASS_DRIVER, SCREENSHOT_NODE)
+            .unwrap();
+    }
+}
@@ -0,0 +1,1820 @@
+use bevy::{
+    input::mouse::MouseWheel,
+    prelude::*,
+    utils::{HashMap, HashSet},
+    winit::WinitWindows,
+};
+use bevy_prototype_lyon::{entity::ShapeBundle, prelude::*};
+use bevy_rapier2d::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    content_offset,
+    coords::{cursor_to_physics, CoordConverter},
+    groups,
+    trails::Trail,
+    zoom::{scrolled_lines, shift_held},
+    PhysicsWindow, PrimaryWindow, WindowBounced, WindowState, WindowWalls,
+};
+
+/// The colour a shape was spawned with.
+#[derive(Component)]
+pub(crate) struct BaseColour(pub(crate) Color);
+
+/// A shape inside the window whose body is `window`.
+#[derive(Component)]
+pub(crate) struct InteriorShape {
+    pub(crate) window: Entity,
+}
+
+/// Seed for the sizes, kinds, colours and starting velocities of the spawned shapes, so a scene can
+/// be reproduced. Unset, the shapes differ from run to run.
+///
+/// Insert this before adding `WindowPhysicsPlugin` to set it.
+#[derive(Default)]
+pub struct SpawnSeed(pub Option<u64>);
+
+// where all spawn randomness comes from, seeded from SpawnSeed once at startup
+pub(crate) struct ShapeRng(pub(crate) StdRng);
+
+impl ShapeRng {
+    pub(crate) fn new(seed: &SpawnSeed) -> Self {
+        Self(match seed.0 {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        })
+    }
+}
+
+/// How many shapes are put in the window at startup, how big they are in physics units, how heavy
+/// and how fast they start out.
+///
+/// Insert this before adding `WindowPhysicsPlugin` to override the defaults.
+pub struct SpawnConfig {
+    pub count: usize,
+    pub min_size: Real,
+    pub max_size: Real,
+    pub density: Real,
+    /// gives every shape this mass, whatever its size, instead of deriving it from `density`
+    pub uniform_mass: Option<Real>,
+    /// sends every shape off in a random direction, instead of starting it at rest
+    pub random_velocity: bool,
+    pub min_speed: Real, // physics units per second, like the window's velocity
+    pub max_speed: Real,
+}
+
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self {
+            count: 10,
+            min_size: 0.01,
+            max_size: 0.04,
+            density: 1.0,
+            uniform_mass: None,
+            random_velocity: false,
+            min_speed: 0.05,
+            max_speed: 0.2,
+        }
+    }
+}
+
+impl SpawnConfig {
+    pub(crate) fn size_range(&self) -> std::ops::RangeInclusive<Real> {
+        if self.min_size <= self.max_size {
+            self.min_size..=self.max_size
+        } else {
+            warn!(
+                "SpawnConfig min_size {} is larger than max_size {}, swapping them",
+                self.min_size, self.max_size
+            );
+            self.max_size..=self.min_size
+        }
+    }
+
+    fn initial_velocity(&self, rng: &mut StdRng) -> RigidBodyVelocity {
+        use rand::Rng;
+        if !self.random_velocity {
+            return RigidBodyVelocity::zero();
+        }
+        let speed =
+            rng.gen_range(self.min_speed.min(self.max_speed)..=self.max_speed.max(self.min_speed));
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        RigidBodyVelocity {
+            linvel: Vector::new(angle.cos(), angle.sin()) * speed,
+            angvel: 0.,
+        }
+    }
+
+    fn mass_properties(&self, area: Real) -> ColliderMassProps {
+        match self.uniform_mass {
+            Some(mass) => ColliderMassProps::Density(mass / area),
+            None => ColliderMassProps::Density(self.density),
+        }
+    }
+}
+
+/// Connects the shapes into a wobbly blob instead of scattering them. Off by default.
+///
+/// The shapes are circles on a grid, each pinned to its right and upper neighbour by a joint whose
+/// motor springs it back to its resting angle. Everything is measured in physics units, so the
+/// blob behaves the same whatever the `PhysicsScale`.
+pub struct JellyConfig {
+    pub enabled: bool,
+    pub columns: usize,
+    pub rows: usize,
+    pub spacing: Real, // between neighbouring centers
+    pub size: Real,    // radius of each circle
+    pub stiffness: Real,
+    pub damping: Real,
+}
+
+impl Default for JellyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            columns: 5,
+            rows: 4,
+            spacing: 0.03,
+            size: 0.012,
+            stiffness: 50.,
+            damping: 2.,
+        }
+    }
+}
+
+// the shapes a window starts with, around `center`
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn spawn_shapes(
+    commands: &mut Commands,
+    converter: &CoordConverter,
+    spawn_config: &SpawnConfig,
+    weights: &ShapeWeights,
+    jelly: &JellyConfig,
+    rng: &mut StdRng,
+    window: (Entity, u32),
+    center: Point<Real>,
+) {
+    use rand::Rng;
+
+    if jelly.enabled {
+        spawn_jelly(
+            commands,
+            converter,
+            spawn_config,
+            jelly,
+            rng,
+            window,
+            center,
+        );
+        return;
+    }
+    let size_range = spawn_config.size_range();
+    for _ in 0..spawn_config.count {
+        let size = rng.gen_range(size_range.clone());
+        spawn_random_shape(
+            commands,
+            converter,
+            spawn_config,
+            weights,
+            rng,
+            window,
+            center,
+            size,
+        );
+    }
+}
+
+fn spawn_jelly(
+    commands: &mut Commands,
+    converter: &CoordConverter,
+    spawn_config: &SpawnConfig,
+    jelly: &JellyConfig,
+    rng: &mut StdRng,
+    window: (Entity, u32),
+    center: Point<Real>,
+) {
+    let origin = center
+        - Vector::new(
+            jelly.columns.saturating_sub(1) as Real,
+            jelly.rows.saturating_sub(1) as Real,
+        ) * jelly.spacing
+            / 2.;
+    let grid: Vec<Vec<Entity>> = (0..jelly.rows)
+        .map(|row| {
+            (0..jelly.columns)
+                .map(|column| {
+                    let position =
+                        origin + Vector::new(column as Real, row as Real) * jelly.spacing;
+                    let spec = ShapeSpec::random_colour(ShapeKind::Circle, jelly.size, rng);
+                    spawn_shape(
+                        commands,
+                        converter,
+                        spawn_config,
+                        rng,
+                        window,
+                        position,
+                        spec,
+                    )
+                })
+                .collect()
+        })
+        .collect();
+
+    let half = jelly.spacing / 2.;
+    let mut link = |a: Entity, b: Entity, offset: Vector<Real>| {
+        let joint = RevoluteJoint::new()
+            .local_anchor1(Point::from(offset * half))
+            .local_anchor2(Point::from(-offset * half))
+            .motor_position(0., jelly.stiffness, jelly.damping);
+        let joint = commands
+            .spawn()
+            .insert(JointBuilderComponent::new(joint, a, b))
+            .id();
+        // despawned along with the shape, e.g. on reset
+        commands.entity(a).add_child(joint);
+    };
+    for (row, shapes) in grid.iter().enumerate() {
+        for (column, &shape) in shapes.iter().enumerate() {
+            if let Some(&right) = shapes.get(column + 1) {
+                link(shape, right, Vector::x());
+            }
+            if let Some(&above) = grid.get(row + 1).map(|shapes| &shapes[column]) {
+                link(shape, above, Vector::y());
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum ShapeKind {
+    Circle,
+    Square,
+    Triangle,
+    Star,
+}
+
+/// How often each kind of shape is picked when one is spawned at random, relative to the others.
+/// A kind weighted 0 is never picked.
+pub struct ShapeWeights {
+    pub circle: f32,
+    pub square: f32,
+    pub triangle: f32,
+    pub star: f32,
+}
+
+impl Default for ShapeWeights {
+    fn default() -> Self {
+        Self {
+            circle: 1.,
+            square: 1.,
+            triangle: 1.,
+            star: 1.,
+        }
+    }
+}
+
+impl ShapeWeights {
+    // falls back to circles if no weight is positive
+    pub(crate) fn pick(&self, rng: &mut StdRng) -> ShapeKind {
+        use rand::seq::SliceRandom;
+        let weighted = [
+            (ShapeKind::Circle, self.circle),
+            (ShapeKind::Square, self.square),
+            (ShapeKind::Triangle, self.triangle),
+            (ShapeKind::Star, self.star),
+        ];
+        match weighted.choose_weighted(rng, |(_, weight)| *weight) {
+            Ok(&(kind, _)) => kind,
+            Err(e) => {
+                warn!(
+                    "Can't pick a shape with these ShapeWeights ({}), spawning a circle",
+                    e
+                );
+                ShapeKind::Circle
+            }
+        }
+    }
+}
+
+// a shape of random kind, see spawn_shape
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn spawn_random_shape(
+    commands: &mut Commands,
+    converter: &CoordConverter,
+    spawn_config: &SpawnConfig,
+    weights: &ShapeWeights,
+    rng: &mut StdRng,
+    window: (Entity, u32),
+    position: Point<Real>,
+    size: Real,
+) -> Entity {
+    let kind = weights.pick(rng);
+    let spec = ShapeSpec::random_colour(kind, size, rng);
+    spawn_shape(
+        commands,
+        converter,
+        spawn_config,
+        rng,
+        window,
+        position,
+        spec,
+    )
+}
+
+/// The order shapes were spawned in, counting up from 0 over the whole run.
+#[derive(Component)]
+pub(crate) struct SpawnIndex(u64);
+
+// the SpawnIndex the next shape gets
+#[derive(Default)]
+pub(crate) struct NextSpawnIndex(u64);
+
+// shapes are indexed the frame after they're spawned, whichever system spawned them
+pub(crate) fn index_new_shapes(
+    mut commands: Commands,
+    mut next: ResMut<NextSpawnIndex>,
+    shapes: Query<Entity, (With<InteriorShape>, Without<SpawnIndex>)>,
+) {
+    for entity in shapes.iter() {
+        commands.entity(entity).insert(SpawnIndex(next.0));
+        next.0 += 1;
+    }
+}
+
+/// What a shape is and what it looks like: a circle of radius `size`, a square of side `size`, or a
+/// triangle or star reaching `size` from its center (physics units).
+#[derive(Component, Clone, Copy)]
+pub(crate) struct ShapeSpec {
+    pub(crate) kind: ShapeKind,
+    pub(crate) size: Real,
+    pub(crate) colour: Color,
+}
+
+impl ShapeSpec {
+    pub(crate) fn random_colour(kind: ShapeKind, size: Real, rng: &mut StdRng) -> Self {
+        use rand::seq::SliceRandom;
+        const COLOURS: &[Color] = &[
+            Color::RED,
+            Color::ORANGE,
+            Color::PINK,
+            Color::BLUE,
+            Color::GOLD,
+        ];
+
+        Self {
+            kind,
+            size,
+            colour: *COLOURS.choose(rng).expect("COLOURS is not empty"),
+        }
+    }
+}
+
+pub(crate) fn spawn_shape(
+    commands: &mut Commands,
+    converter: &CoordConverter,
+    spawn_config: &SpawnConfig,
+    rng: &mut StdRng,
+    (window, interior): (Entity, u32),
+    position: Point<Real>,
+    spec: ShapeSpec,
+) -> Entity {
+    let ShapeSpec { kind, size, colour } = spec;
+    let mode = DrawMode::Fill(FillMode::color(colour));
+
+    let (gbundle, cshape, area) = {
+        match kind {
+            ShapeKind::Circle => (
+                GeometryBuilder::build_as(
+                    &shapes::Circle {
+                        radius: size * converter.physics_scale,
+                        ..Default::default()
+                    },
+                    mode,
+                    Transform::default(),
+                ),
+                ColliderShape::ball(size).into(),
+                std::f32::consts::PI * size * size,
+            ),
+            ShapeKind::Square => (
+                GeometryBuilder::build_as(
+                    &shapes::Rectangle {
+                        extents: Vec2::from([size, size]) * converter.physics_scale,
+                        origin: RectangleOrigin::Center,
+                    },
+                    mode,
+                    Transform::default(),
+                ),
+                ColliderShape::cuboid(size / 2.0, size / 2.0),
+                size * size,
+            ),
+            ShapeKind::Triangle => {
+                let points = star_points(3, size, size / 2.);
+                let points = [points[0], points[2], points[4]];
+                (
+                    polygon_geometry(&points, converter, mode),
+                    triangle_collider(&points, size),
+                    polygon_area(&points),
+                )
+            }
+            ShapeKind::Star => {
+                let points = star_points(5, size, size * 0.45);
+                (
+                    polygon_geometry(&points, converter, mode),
+                    star_collider(&points, size),
+                    polygon_area(&points),
+                )
+            }
+        }
+    };
+
+    commands
+        .spawn()
+        .insert_bundle(gbundle)
+        .insert_bundle(RigidBodyBundle {
+            position: Isometry::new(position.coords, 0.).into(),
+            velocity: spawn_config.initial_velocity(rng).into(),
+            // CCD is switched on and off with their speed, see ContinuousCollision
+            ..Default::default()
+        })
+        .insert_bundle(ColliderBundle {
+            shape: cshape.into(),
+            mass_properties: spawn_config.mass_properties(area).into(),
+            material: ColliderMaterial::new(0.3, 0.5).into(),
+            flags: ColliderFlags {
+                // only the window's own walls and shapes
+                collision_groups: groups::interior_shapes(interior),
+                // counted in CollisionStats
+                active_events: ActiveEvents::CONTACT_EVENTS,
+                ..Default::default()
+            }
+            .into(),
+            ..Default::default()
+        })
+        .insert(RigidBodyPositionSync::default())
+        .insert(InteriorShape { window })
+        .insert(Trail::new(colour))
+        .insert(BaseColour(colour))
+        .insert(spec)
+        .id()
+}
+
+// parry panics building the hull of points that all coincide, which a shape of size 0 has. Those
+// get a ball of their size instead
+fn hull(points: &[Point<Real>], size: Real) -> Option<ColliderShape> {
+    if size > Real::EPSILON {
+        ColliderShape::convex_hull(points)
+    } else {
+        None
+    }
+}
+
+fn triangle_collider(points: &[Point<Real>], size: Real) -> ColliderShape {
+    hull(points, size).unwrap_or_else(|| ColliderShape::ball(size))
+}
+
+// the star is concave, so it is made of its inner pentagon and a triangle per tip
+fn star_collider(points: &[Point<Real>], size: Real) -> ColliderShape {
+    let inner: Vec<_> = points.iter().skip(1).step_by(2).copied().collect();
+    let inner = match hull(&inner, size) {
+        Some(inner) => inner,
+        None => return ColliderShape::ball(size),
+    };
+    let mut parts = vec![(Isometry::identity(), inner)];
+    for i in (0..points.len()).step_by(2) {
+        let before = points[(i + points.len() - 1) % points.len()];
+        let after = points[i + 1];
+        parts.push((
+            Isometry::identity(),
+            ColliderShape::triangle(before, points[i], after),
+        ));
+    }
+    ColliderShape::compound(parts)
+}
+
+// corners of a star with `tips` points, going anticlockwise from the top tip and alternating
+// between `outer` and `inner` distance from the center
+fn star_points(tips: usize, outer: Real, inner: Real) -> Vec<Point<Real>> {
+    (0..2 * tips)
+        .map(|i| {
+            let angle =
+                std::f32::consts::FRAC_PI_2 + i as Real * std::f32::consts::PI / tips as Real;
+            let radius = if i % 2 == 0 { outer } else { inner };
+            Point::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+fn polygon_geometry(
+    points: &[Point<Real>],
+    converter: &CoordConverter,
+    mode: DrawMode,
+) -> ShapeBundle {
+    GeometryBuilder::build_as(
+        &shapes::Polygon {
+            points: points
+                .iter()
+                .map(|p| Vec2::new(p.x, p.y) * converter.physics_scale)
+                .collect(),
+            closed: true,
+        },
+        mode,
+        Transform::default(),
+    )
+}
+
+// shoelace formula, for points going anticlockwise
+fn polygon_area(points: &[Point<Real>]) -> Real {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<Real>()
+        / 2.
+}
+
+// the window the cursor is over, and where it is in physics
+fn hovered_window(
+    windows: &Windows,
+    winit_windows: &WinitWindows,
+    window_query: &Query<(Entity, &PhysicsWindow)>,
+    converter: &CoordConverter,
+) -> Option<((Entity, u32), Point<Real>)> {
+    window_query.iter().find_map(|(entity, physics_window)| {
+        let point = cursor_to_physics(windows, winit_windows, physics_window.id, converter)?;
+        Some(((entity, physics_window.interior), point))
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn n_spawns_shape_at_cursor(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    window_query: Query<(Entity, &PhysicsWindow)>,
+    converter: Res<CoordConverter>,
+    spawn_config: Res<SpawnConfig>,
+    weights: Res<ShapeWeights>,
+    mut rng: ResMut<ShapeRng>,
+) {
+    use rand::Rng;
+
+    if !keys.just_pressed(KeyCode::N) {
+        return;
+    }
+    let (window, position) =
+        match hovered_window(&windows, &winit_windows, &window_query, &converter) {
+            Some(hovered) => hovered,
+            None => return,
+        };
+
+    let size = rng.0.gen_range(spawn_config.size_range());
+    spawn_random_shape(
+        &mut commands,
+        &converter,
+        &spawn_config,
+        &weights,
+        &mut rng.0,
+        window,
+        position,
+        size,
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn right_click_deletes_shape(
+    mut commands: Commands,
+    mouse_button: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    window_query: Query<(Entity, &PhysicsWindow)>,
+    converter: Res<CoordConverter>,
+    query_pipeline: Res<QueryPipeline>,
+    collider_query: QueryPipelineColliderComponentsQuery,
+    shapes: Query<Entity, With<InteriorShape>>,
+) {
+    if !mouse_button.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let ((_, interior), point) =
+        match hovered_window(&windows, &winit_windows, &window_query, &converter) {
+            Some(hovered) => hovered,
+            None => return,
+        };
+
+    let colliders = QueryPipelineColliderComponentsSet(&collider_query);
+    // the walls share the shapes' group, so check what was hit as well
+    let groups = groups::interior_shapes(interior);
+    let mut hit = None;
+    query_pipeline.intersections_with_point(&colliders, &point, groups, None, |handle| {
+        if shapes.get(handle.entity()).is_ok() {
+            hit = Some(handle.entity());
+            false
+        } else {
+            true
+        }
+    });
+
+    if let Some(shape) = hit {
+        // the trail pieces are children of the shape
+        commands.entity(shape).despawn_recursive();
+    }
+}
+
+/// The most shapes scrolling the mouse wheel up with shift held fills a window with. Scrolling down
+/// takes the oldest ones away again, confetti and sparks aside.
+pub struct ShapeLimit(pub usize);
+
+impl Default for ShapeLimit {
+    fn default() -> Self {
+        Self(100)
+    }
+}
+
+// a shape at the cursor for every line scrolled up, and one fewer of the oldest for every line down
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn shift_scroll_changes_shape_count(
+    mut commands: Commands,
+    mut wheel_events: EventReader<MouseWheel>,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    window_query: Query<(Entity, &PhysicsWindow)>,
+    converter: Res<CoordConverter>,
+    spawn_config: Res<SpawnConfig>,
+    weights: Res<ShapeWeights>,
+    limit: Res<ShapeLimit>,
+    mut rng: ResMut<ShapeRng>,
+    shapes: Query<(Entity, &InteriorShape, &SpawnIndex), Without<Lifetime>>,
+    mut unused_lines: Local<f32>,
+) {
+    use rand::Rng;
+
+    let lines = scrolled_lines(&mut wheel_events);
+    if !shift_held(&keys) {
+        *unused_lines = 0.;
+        return;
+    }
+    *unused_lines += lines;
+    let steps = unused_lines.trunc();
+    *unused_lines -= steps;
+    if steps == 0. {
+        return;
+    }
+    let (window, position) =
+        match hovered_window(&windows, &winit_windows, &window_query, &converter) {
+            Some(hovered) => hovered,
+            None => return,
+        };
+
+    let mut in_window: Vec<_> = shapes
+        .iter()
+        .filter(|(_, shape, _)| shape.window == window.0)
+        .map(|(entity, _, index)| (index.0, entity))
+        .collect();
+    if steps > 0. {
+        let room = limit.0.saturating_sub(in_window.len());
+        for _ in 0..(steps as usize).min(room) {
+            let size = rng.0.gen_range(spawn_config.size_range());
+            spawn_random_shape(
+                &mut commands,
+                &converter,
+                &spawn_config,
+                &weights,
+                &mut rng.0,
+                window,
+                position,
+                size,
+            );
+        }
+    } else {
+        in_window.sort_unstable();
+        for &(_, shape) in in_window.iter().take(-steps as usize) {
+            // the trail pieces are children of the shape
+            commands.entity(shape).despawn_recursive();
+        }
+    }
+}
+
+/// Z stopping every shape dead, and the bouncing windows too with `include_window`. Unlike pausing,
+/// physics carries on, so gravity starts them falling again straight away.
+#[derive(Default)]
+pub struct FreezeConfig {
+    pub include_window: bool,
+}
+
+// sleeping bodies are already still, they're woken so they fall with the rest
+pub(crate) fn z_freezes_shapes(
+    keys: Res<Input<KeyCode>>,
+    freeze: Res<FreezeConfig>,
+    mut shapes: Query<
+        (
+            &mut RigidBodyVelocityComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        (With<InteriorShape>, Without<WindowState>),
+    >,
+    mut bodies: Query<
+        (
+            &WindowState,
+            &mut RigidBodyVelocityComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        Without<InteriorShape>,
+    >,
+) {
+    if !keys.just_pressed(KeyCode::Z) {
+        return;
+    }
+    for (mut velocity, mut activation) in shapes.iter_mut() {
+        *velocity = RigidBodyVelocity::zero().into();
+        activation.wake_up(true);
+    }
+    if freeze.include_window {
+        for (state, mut velocity, mut activation) in bodies.iter_mut() {
+            if let WindowState::Bouncing = state {
+                *velocity = RigidBodyVelocity::zero().into();
+                activation.wake_up(true);
+            }
+        }
+    }
+}
+
+/// The shapes floating weightless inside the windows while true, toggled with 0, as the windows
+/// themselves still fall. Only the walls move the shapes then, so a flung window still knocks them
+/// about. Off by default.
+#[derive(Default)]
+pub struct ZeroGInterior(pub bool);
+
+pub(crate) fn digit_0_toggles_zero_g(keys: Res<Input<KeyCode>>, mut zero_g: ResMut<ZeroGInterior>) {
+    if keys.just_pressed(KeyCode::Key0) {
+        zero_g.0 = !zero_g.0;
+    }
+}
+
+// rapier's gravity is shared by every body, so the shapes opt out of it with their gravity scale.
+// New shapes pick up the current value too, and they're all woken so they fall again when it's
+// switched off
+pub(crate) fn apply_zero_g_interior(
+    zero_g: Res<ZeroGInterior>,
+    mut shapes: Query<
+        (
+            &mut RigidBodyForcesComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        (With<InteriorShape>, Without<WindowState>),
+    >,
+) {
+    let scale = if zero_g.0 { 0. } else { 1. };
+    for (mut forces, mut activation) in shapes.iter_mut() {
+        if zero_g.is_changed() || forces.is_added() {
+            forces.gravity_scale = scale;
+            activation.wake_up(true);
+        }
+    }
+}
+
+/// The shapes let out of their windows while true, toggled with Tab. They pass through the walls
+/// and roam the desktop behind the windows, bouncing off the monitor edges and each other instead.
+/// Switched back, the shapes still inside a window are held in by its walls again, and the rest
+/// fall away and are dealt with like any escaped shape, see [`EscapeConfig`]. Off by default.
+#[derive(Default)]
+pub struct ShapesRoam(pub bool);
+
+pub(crate) fn tab_toggles_roaming_shapes(keys: Res<Input<KeyCode>>, mut roam: ResMut<ShapesRoam>) {
+    if keys.just_pressed(KeyCode::Tab) {
+        roam.0 = !roam.0;
+    }
+}
+
+// new shapes pick up the current groups too, like apply_bounciness
+pub(crate) fn apply_shape_roaming(
+    roam: Res<ShapesRoam>,
+    windows: Query<&PhysicsWindow>,
+    mut shapes: Query<(&InteriorShape, &mut ColliderFlagsComponent)>,
+) {
+    for (shape, mut flags) in shapes.iter_mut() {
+        if !roam.is_changed() && !flags.is_added() {
+            continue;
+        }
+        let collision_groups = if roam.0 {
+            groups::roaming_shapes()
+        } else {
+            match windows.get(shape.window) {
+                Ok(window) => groups::interior_shapes(window.interior),
+                // the window is gone, escaped_shapes_drain takes care of it
+                Err(_) => continue,
+            }
+        };
+        flags.collision_groups = collision_groups;
+    }
+}
+
+/// The explosion set off at the cursor with E, pushing the shapes of the window under it away.
+pub struct ExplosionConfig {
+    pub radius: Real,   // physics units, nothing further away is pushed
+    pub strength: Real, // impulse right at the cursor, falling off to nothing at `radius`
+    /// pushes the window's body too, when it is bouncing
+    pub include_window: bool,
+}
+
+impl Default for ExplosionConfig {
+    fn default() -> Self {
+        Self {
+            radius: 0.15,
+            strength: 0.0005,
+            include_window: false,
+        }
+    }
+}
+
+impl ExplosionConfig {
+    fn impulse(&self, center: Point<Real>, p: Point<Real>) -> Option<Vector<Real>> {
+        let offset = p - center;
+        let distance = offset.norm();
+        if distance >= self.radius || distance <= Real::EPSILON {
+            return None;
+        }
+        Some(offset / distance * self.strength * (1. - distance / self.radius))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn e_explodes_at_cursor(
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    window_query: Query<(Entity, &PhysicsWindow)>,
+    converter: Res<CoordConverter>,
+    explosion: Res<ExplosionConfig>,
+    mut shapes: Query<
+        (
+            &InteriorShape,
+            &RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+            &RigidBodyMassPropsComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        Without<WindowState>,
+    >,
+    mut bodies: Query<
+        (
+            &WindowState,
+            &RigidBodyPositionComponent,
+            &mut RigidBodyVelocityComponent,
+            &RigidBodyMassPropsComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        Without<InteriorShape>,
+    >,
+) {
+    if !keys.just_pressed(KeyCode::E) {
+        return;
+    }
+    let ((window, _), center) =
+        match hovered_window(&windows, &winit_windows, &window_query, &converter) {
+            Some(hovered) => hovered,
+            None => return,
+        };
+
+    for (shape, position, mut velocity, rbmp, mut activation) in shapes.iter_mut() {
+        if shape.window != window {
+            continue;
+        }
+        let p = Point::from(position.position.translation.vector);
+        if let Some(impulse) = explosion.impulse(center, p) {
+            velocity.apply_impulse(rbmp, impulse);
+            activation.wake_up(true);
+        }
+    }
+
+    if explosion.include_window {
+        if let Ok((WindowState::Bouncing, position, mut velocity, rbmp, mut activation)) =
+            bodies.get_mut(window)
+        {
+            let p = Point::from(position.position.translation.vector);
+            if let Some(impulse) = explosion.impulse(center, p) {
+                velocity.apply_impulse(rbmp, impulse);
+                activation.wake_up(true);
+            }
+        }
+    }
+}
+
+/// The gravity well at the cursor while G is held, pulling the shapes of the window under it in.
+pub struct WellConfig {
+    pub strength: Real, // force at one physics unit from the cursor, growing closer in
+    // physics units, closer shapes are pulled as if they were this far, so the force stays finite
+    // at the cursor
+    pub min_distance: Real,
+}
+
+impl Default for WellConfig {
+    fn default() -> Self {
+        Self {
+            strength: 0.0005,
+            min_distance: 0.02,
+        }
+    }
+}
+
+// added to the force after it's cleared, so letting go of G, or the cursor leaving the window,
+// stops the pull
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn g_pulls_shapes_to_cursor(
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    window_query: Query<(Entity, &PhysicsWindow)>,
+    converter: Res<CoordConverter>,
+    well: Res<WellConfig>,
+    mut shapes: Query<(
+        &InteriorShape,
+        &RigidBodyPositionComponent,
+        &mut RigidBodyForcesComponent,
+        &mut RigidBodyActivationComponent,
+    )>,
+) {
+    if !keys.pressed(KeyCode::G) {
+        return;
+    }
+    let ((window, _), cursor) =
+        match hovered_window(&windows, &winit_windows, &window_query, &converter) {
+            Some(hovered) => hovered,
+            None => return,
+        };
+
+    for (shape, position, mut forces, mut activation) in shapes.iter_mut() {
+        if shape.window != window {
+            continue;
+        }
+        let offset = cursor - Point::from(position.position.translation.vector);
+        let distance = offset.norm();
+        if distance > Real::EPSILON {
+            activation.wake_up(true);
+            forces.force += offset / distance * well.strength / distance.max(well.min_distance);
+        }
+    }
+}
+
+/// The pull of every shape back to the middle of its window for `duration` seconds, started with
+/// V. Like gravity it accelerates heavy and light shapes alike, and `strength` is a few times
+/// gravity's so it wins while it lasts. Within `ease_distance` of the middle it tapers off, so the
+/// shapes settle against each other rather than slamming together.
+pub struct VacuumConfig {
+    pub strength: Real, // physics units per second squared
+    pub duration: f32,  // seconds
+    pub ease_distance: Real,
+}
+
+impl Default for VacuumConfig {
+    fn default() -> Self {
+        Self {
+            strength: 30.,
+            duration: 1.,
+            ease_distance: 0.05,
+        }
+    }
+}
+
+// added to the force after it's cleared, like the well
+pub(crate) fn v_vacuums_shapes_to_center(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    vacuum: Res<VacuumConfig>,
+    mut time_left: Local<f32>,
+    window_query: Query<&RigidBodyPositionComponent, With<PhysicsWindow>>,
+    mut shapes: Query<(
+        &InteriorShape,
+        &RigidBodyPositionComponent,
+        &RigidBodyMassPropsComponent,
+        &mut RigidBodyForcesComponent,
+        &mut RigidBodyActivationComponent,
+    )>,
+) {
+    if keys.just_pressed(KeyCode::V) {
+        *time_left = vacuum.duration;
+    }
+    if *time_left <= 0. {
+        return;
+    }
+    *time_left -= time.delta_seconds();
+
+    for (shape, position, rbmp, mut forces, mut activation) in shapes.iter_mut() {
+        let center = match window_query.get(shape.window) {
+            Ok(window) => window.position.translation.vector,
+            Err(_) => continue,
+        };
+        let offset = center - position.position.translation.vector;
+        let distance = offset.norm();
+        if distance > Real::EPSILON {
+            let ease = (distance / vacuum.ease_distance).min(1.);
+            activation.wake_up(true);
+            forces.force +=
+                offset / distance * vacuum.strength * ease * rbmp.local_mprops.inv_mass.recip();
+        }
+    }
+}
+
+/// Gravity pointing at the cursor instead of down, as if it were a planet. While `enabled` the
+/// global gravity is switched off and every shape, and the bouncing windows if `include_window`,
+/// is pulled towards where the cursor last was over one of the windows. The pull falls off with
+/// the square of the distance, as `strength` over the distance squared in physics units per second
+/// squared, and is capped at what it would be `min_distance` away.
+pub struct PlanetGravity {
+    pub enabled: bool,
+    pub strength: Real,
+    pub min_distance: Real,
+    pub include_window: bool,
+}
+
+impl Default for PlanetGravity {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strength: 0.5,
+            min_distance: 0.05,
+            include_window: false,
+        }
+    }
+}
+
+impl PlanetGravity {
+    // the acceleration towards `planet` of a body at `p`
+    fn pull(&self, planet: Point<Real>, p: Point<Real>) -> Vector<Real> {
+        let offset = planet - p;
+        let distance = offset.norm();
+        if distance <= Real::EPSILON {
+            return Vector::zeros();
+        }
+        offset / distance * self.strength / distance.max(self.min_distance).powi(2)
+    }
+}
+
+// added to the force after it's cleared. The cursor is only known while it's over one of the
+// windows, so the planet stays where it was last seen
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn shapes_fall_towards_cursor(
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    window_query: Query<(Entity, &PhysicsWindow)>,
+    converter: Res<CoordConverter>,
+    planet: Res<PlanetGravity>,
+    mut last_cursor: Local<Option<Point<Real>>>,
+    mut shapes: Query<
+        (
+            &RigidBodyPositionComponent,
+            &RigidBodyMassPropsComponent,
+            &mut RigidBodyForcesComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        (With<InteriorShape>, Without<WindowState>),
+    >,
+    mut bodies: Query<
+        (
+            &WindowState,
+            &RigidBodyPositionComponent,
+            &RigidBodyMassPropsComponent,
+            &mut RigidBodyForcesComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        Without<InteriorShape>,
+    >,
+) {
+    if !planet.enabled {
+        return;
+    }
+    if let Some((_, cursor)) = hovered_window(&windows, &winit_windows, &window_query, &converter) {
+        *last_cursor = Some(cursor);
+    }
+    let cursor = match *last_cursor {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    for (position, rbmp, mut forces, mut activation) in shapes.iter_mut() {
+        let p = Point::from(position.position.translation.vector);
+        forces.force += planet.pull(cursor, p) * rbmp.local_mprops.inv_mass.recip();
+        activation.wake_up(true);
+    }
+    if planet.include_window {
+        for (state, position, rbmp, mut forces, mut activation) in bodies.iter_mut() {
+            if let WindowState::Bouncing = state {
+                let p = Point::from(position.position.translation.vector);
+                forces.force += planet.pull(cursor, p) * rbmp.local_mprops.inv_mass.recip();
+                activation.wake_up(true);
+            }
+        }
+    }
+}
+
+/// Shapes sticking to the wall of their window they hit, as if magnetic, toggled with M. Each one
+/// lets go after `hold` seconds, and they all do when it's switched off. Off by default.
+pub struct WallMagnet {
+    pub enabled: bool,
+    pub hold: f32, // seconds
+}
+
+impl Default for WallMagnet {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hold: 2.,
+        }
+    }
+}
+
+/// The joint holding a shape to its window, and seconds until it lets go.
+#[derive(Component)]
+pub(crate) struct Clinging {
+    joint: Entity,
+    time_left: f32,
+}
+
+fn let_go(commands: &mut Commands, shape: Entity, clinging: &Clinging) {
+    commands.entity(clinging.joint).despawn_recursive();
+    commands.entity(shape).remove::<Clinging>();
+}
+
+pub(crate) fn m_toggles_wall_magnet(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut magnet: ResMut<WallMagnet>,
+    clinging: Query<(Entity, &Clinging)>,
+) {
+    if !keys.just_pressed(KeyCode::M) {
+        return;
+    }
+    magnet.enabled = !magnet.enabled;
+    if !magnet.enabled {
+        for (shape, clinging) in clinging.iter() {
+            let_go(&mut commands, shape, clinging);
+        }
+    }
+}
+
+// the walls are colliders of the window's body, so the shape is jointed to the body where it is
+// now. The joint is the shape's child, so it goes when the shape is despawned
+pub(crate) fn shapes_cling_to_walls(
+    mut commands: Commands,
+    time: Res<Time>,
+    magnet: Res<WallMagnet>,
+    mut contact_events: EventReader<ContactEvent>,
+    walls: Query<&Parent, With<WindowWalls>>,
+    window_bodies: Query<&RigidBodyPositionComponent, With<WindowState>>,
+    shapes: Query<&RigidBodyPositionComponent, (With<InteriorShape>, Without<Clinging>)>,
+    mut clinging: Query<(Entity, &mut Clinging)>,
+) {
+    for (shape, mut clinging) in clinging.iter_mut() {
+        clinging.time_left -= time.delta_seconds();
+        if clinging.time_left <= 0. {
+            let_go(&mut commands, shape, &clinging);
+        }
+    }
+
+    // a shape can hit the wall in more than one place in a frame
+    let mut stuck = Vec::new();
+    for event in contact_events.iter() {
+        let (h1, h2) = match event {
+            ContactEvent::Started(h1, h2) if magnet.enabled => (h1, h2),
+            _ => continue,
+        };
+        let hit = [(h1, h2), (h2, h1)].into_iter().find_map(|(wall, shape)| {
+            let window = walls.get(wall.entity()).ok()?.0;
+            let shape = shape.entity();
+            Some((
+                window,
+                window_bodies.get(window).ok()?,
+                shape,
+                shapes.get(shape).ok()?,
+            ))
+        });
+        let (window, window_position, shape, shape_position) = match hit {
+            Some(hit) if !stuck.contains(&hit.2) => hit,
+            _ => continue,
+        };
+
+        let anchor = window_position.position.inverse() * shape_position.position;
+        let joint = FixedJoint::new().local_frame1(anchor);
+        let joint = commands
+            .spawn()
+            .insert(JointBuilderComponent::new(joint, window, shape))
+            .id();
+        commands.entity(shape).add_child(joint).insert(Clinging {
+            joint,
+            time_left: magnet.hold,
+        });
+        stuck.push(shape);
+    }
+}
+
+/// The heavy ball dropped from the top of the primary window with B, plowing through the other
+/// shapes. It is a circle of radius `size`, `density` times as dense as them, thrown down at
+/// `speed` in physics units per second.
+pub struct BowlingConfig {
+    pub size: Real,
+    pub density: Real,
+    pub speed: Real,
+}
+
+impl Default for BowlingConfig {
+    fn default() -> Self {
+        Self {
+            size: 0.06,
+            density: 10.,
+            speed: 0.5,
+        }
+    }
+}
+
+/// A ball dropped with B. Always has CCD, see ContinuousCollision.
+#[derive(Component)]
+pub(crate) struct BowlingBall;
+
+// the ball goes in just below the top wall, in the middle of the content area
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn b_drops_bowling_ball(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    winit_windows: Res<WinitWindows>,
+    converter: Res<CoordConverter>,
+    spawn_config: Res<SpawnConfig>,
+    bowling: Res<BowlingConfig>,
+    mut rng: ResMut<ShapeRng>,
+    window_query: Query<(Entity, &PhysicsWindow, &RigidBodyPositionComponent), With<PrimaryWindow>>,
+) {
+    if !keys.just_pressed(KeyCode::B) {
+        return;
+    }
+    let (entity, physics_window, body) = match window_query.get_single() {
+        Ok(window) => window,
+        Err(e) => {
+            debug!("No primary window to drop a bowling ball in: {}", e);
+            return;
+        }
+    };
+    let window = match winit_windows.get_window(physics_window.id) {
+        Some(window) => window,
+        None => return,
+    };
+    let inner_size = converter.to_physics_vec(converter.from_physical_size(window.inner_size()));
+    let position = Point::from(body.position.translation.vector)
+        + content_offset(window, &converter)
+        + Vector::y() * (inner_size[1] / 2. - bowling.size * 1.5);
+
+    let ball = spawn_shape(
+        &mut commands,
+        &converter,
+        &spawn_config,
+        &mut rng.0,
+        (entity, physics_window.interior),
+        position,
+        ShapeSpec {
+            kind: ShapeKind::Circle,
+            size: bowling.size,
+            colour: Color::BLACK,
+        },
+    );
+    commands
+        .entity(ball)
+        .insert(ColliderMassPropsComponent::from(
+            ColliderMassProps::Density(spawn_config.density * bowling.density),
+        ))
+        .insert(RigidBodyVelocityComponent::from(RigidBodyVelocity {
+            linvel: -Vector::y() * bowling.speed,
+            angvel: 0.,
+        }))
+        .insert(BowlingBall);
+}
+
+/// The burst of tiny circles spawned at the cursor with C, flying outwards until they run out of
+/// lifetime.
+pub struct ConfettiConfig {
+    pub count: usize,
+    pub size: Real,                            // radius, physics units
+    pub speed: std::ops::RangeInclusive<Real>, // physics units per second
+    pub lifetime: f32,                         // seconds
+    // confetti pieces alive at once, further bursts only top it up so the solver isn't swamped
+    pub max_pieces: usize,
+}
+
+impl Default for ConfettiConfig {
+    fn default() -> Self {
+        Self {
+            count: 50,
+            size: 0.003,
+            speed: 0.2..=0.6,
+            lifetime: 3.,
+            max_pieces: 200,
+        }
+    }
+}
+
+/// Seconds left before the shape is despawned.
+#[derive(Component)]
+pub(crate) struct Lifetime(f32);
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn c_bursts_confetti(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    winit_windows: Res<WinitWindows>,
+    window_query: Query<(Entity, &PhysicsWindow)>,
+    converter: Res<CoordConverter>,
+    spawn_config: Res<SpawnConfig>,
+    confetti: Res<ConfettiConfig>,
+    mut rng: ResMut<ShapeRng>,
+    pieces: Query<Entity, (With<Lifetime>, Without<Spark>)>,
+) {
+    use rand::Rng;
+
+    if !keys.just_pressed(KeyCode::C) {
+        return;
+    }
+    let (window, position) =
+        match hovered_window(&windows, &winit_windows, &window_query, &converter) {
+            Some(hovered) => hovered,
+            None => return,
+        };
+
+    let count = confetti
+        .count
+        .min(confetti.max_pieces.saturating_sub(pieces.iter().count()));
+    let rng = &mut rng.0;
+    for _ in 0..count {
+        let spec = ShapeSpec::random_colour(ShapeKind::Circle, confetti.size, rng);
+        let piece = spawn_shape(
+            &mut commands,
+            &converter,
+            &spawn_config,
+            rng,
+            window,
+            position,
+            spec,
+        );
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let speed = rng.gen_range(confetti.speed.clone());
+        commands
+            .entity(piece)
+            .insert(RigidBodyVelocityComponent::from(RigidBodyVelocity {
+                linvel: Vector::new(angle.cos(), angle.sin()) * speed,
+                angvel: 0.,
+            }))
+            .insert(Lifetime(confetti.lifetime));
+    }
+}
+
+/// The few tiny circles thrown from where a window hits a monitor edge at `min_speed`, in physics
+/// units per second, or faster. They start just inside the window's walls and fly away from the
+/// edge, up to `max_sparks` alive at once, apart from the confetti's.
+pub struct SparkConfig {
+    pub count: usize,
+    pub size: Real,                            // radius, physics units
+    pub speed: std::ops::RangeInclusive<Real>, // physics units per second
+    pub lifetime: f32,                         // seconds
+    pub min_speed: Real,
+    pub max_sparks: usize,
+}
+
+impl Default for SparkConfig {
+    fn default() -> Self {
+        Self {
+            count: 5,
+            size: 0.004,
+            speed: 0.3..=0.8,
+            lifetime: 0.5,
+            min_speed: 0.3,
+            max_sparks: 50,
+        }
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct Spark;
+
+// seconds after a window's sparks before it throws more, so one resting in a corner, touching the
+// edges again and again, doesn't spew them
+const SPARK_DEBOUNCE: f64 = 0.2;
+// radians either side of straight away from the edge a spark can fly off at
+const SPARK_SPREAD: Real = std::f32::consts::FRAC_PI_4;
+
+// the contact is on the outside of the window's frame, so the sparks start at the nearest point to
+// it inside the walls. Their transforms follow their bodies like any shape's, which puts them on
+// screen
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn bounces_throw_sparks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut bounces: EventReader<WindowBounced>,
+    winit_windows: Res<WinitWindows>,
+    window_query: Query<(&PhysicsWindow, &RigidBodyPositionComponent)>,
+    converter: Res<CoordConverter>,
+    spawn_config: Res<SpawnConfig>,
+    sparks: Res<SparkConfig>,
+    mut rng: ResMut<ShapeRng>,
+    live_sparks: Query<Entity, With<Spark>>,
+    mut last_sparked: Local<HashMap<Entity, f64>>,
+) {
+    use rand::Rng;
+
+    let now = time.seconds_since_startup();
+    let mut live = live_sparks.iter().count();
+    for bounce in bounces.iter() {
+        if bounce.speed < sparks.min_speed {
+            continue;
+        }
+        let (physics_window, body) = match window_query.get(bounce.window) {
+            Ok(window) => window,
+            Err(_) => continue,
+        };
+        let window = match winit_windows.get_window(physics_window.id) {
+            Some(window) => window,
+            None => continue,
+        };
+        let debouncing = last_sparked
+            .get(&bounce.window)
+            .map_or(false, |&last| now - last < SPARK_DEBOUNCE);
+        if debouncing {
+            continue;
+        }
+        last_sparked.insert(bounce.window, now);
+
+        let inner_size =
+            converter.to_physics_vec(converter.from_physical_size(window.inner_size()));
+        let center =
+            Point::from(body.position.translation.vector) + content_offset(window, &converter);
+        // clear of the walls by a spark's width
+        let reach = (inner_size / 2. - Vector::repeat(sparks.size * 2.)).sup(&Vector::zeros());
+        let start = center + (bounce.point - center).sup(&-reach).inf(&reach);
+        let away = (start - bounce.point)
+            .try_normalize(Real::EPSILON)
+            .or_else(|| (center - bounce.point).try_normalize(Real::EPSILON))
+            .unwrap_or_else(Vector::y);
+
+        let count = sparks.count.min(sparks.max_sparks.saturating_sub(live));
+        live += count;
+        let rng = &mut rng.0;
+        for _ in 0..count {
+            let spec = ShapeSpec::random_colour(ShapeKind::Circle, sparks.size, rng);
+            let spark = spawn_shape(
+                &mut commands,
+                &converter,
+                &spawn_config,
+                rng,
+                (bounce.window, physics_window.interior),
+                start,
+                spec,
+            );
+            let angle = away.y.atan2(away.x) + rng.gen_range(-SPARK_SPREAD..=SPARK_SPREAD);
+            let speed = rng.gen_range(sparks.speed.clone());
+            commands
+                .entity(spark)
+                .insert(RigidBodyVelocityComponent::from(RigidBodyVelocity {
+                    linvel: Vector::new(angle.cos(), angle.sin()) * speed,
+                    angvel: 0.,
+                }))
+                .insert(Lifetime(sparks.lifetime))
+                .insert(Spark);
+        }
+    }
+}
+
+/// Shapes breaking apart when they hit each other hard. When two shapes meet at `min_speed` or
+/// faster, relative to each other in physics units per second, the larger one splits into two of
+/// its kind with half its area each, sent apart at `split_speed`. Shapes are never split below
+/// `min_size`. Off by default.
+pub struct ShatterConfig {
+    pub enabled: bool,
+    pub min_speed: Real,
+    pub min_size: Real, // physics units, see ShapeSpec
+    pub split_speed: Real,
+}
+
+impl Default for ShatterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_speed: 0.5,
+            min_size: 0.008,
+            split_speed: 0.05,
+        }
+    }
+}
+
+// rapier only reports that a contact started, not how hard it pushes, so the speed of the impact
+// stands in for its force. The pieces share the shape's momentum, each getting the velocity that
+// keeps it the same, plus or minus their split
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn collisions_shatter_shapes(
+    mut commands: Commands,
+    mut contact_events: EventReader<ContactEvent>,
+    converter: Res<CoordConverter>,
+    spawn_config: Res<SpawnConfig>,
+    shatter: Res<ShatterConfig>,
+    mut rng: ResMut<ShapeRng>,
+    window_query: Query<&PhysicsWindow>,
+    shapes: Query<
+        (
+            &ShapeSpec,
+            &InteriorShape,
+            &RigidBodyPositionComponent,
+            &RigidBodyVelocityComponent,
+            &RigidBodyMassPropsComponent,
+        ),
+        Without<Lifetime>,
+    >,
+) {
+    let mut shattered = HashSet::default();
+    for event in contact_events.iter() {
+        let (h1, h2) = match event {
+            ContactEvent::Started(h1, h2) if shatter.enabled => (h1.entity(), h2.entity()),
+            _ => continue,
+        };
+        let (a, b) = match (shapes.get(h1), shapes.get(h2)) {
+            (Ok(a), Ok(b)) => (a, b),
+            _ => continue,
+        };
+        if (a.3.linvel - b.3.linvel).norm() < shatter.min_speed {
+            continue;
+        }
+        let (entity, (spec, shape, position, velocity, rbmp)) = if a.0.size >= b.0.size {
+            (h1, a)
+        } else {
+            (h2, b)
+        };
+        let size = spec.size * std::f32::consts::FRAC_1_SQRT_2;
+        if size < shatter.min_size || !shattered.insert(entity) {
+            continue;
+        }
+        let interior = match window_query.get(shape.window) {
+            Ok(physics_window) => physics_window.interior,
+            Err(_) => continue,
+        };
+
+        let mass = rbmp.local_mprops.inv_mass.recip();
+        let piece_mass = spawn_config.uniform_mass.unwrap_or(mass / 2.);
+        let linvel = velocity.linvel * mass / (2. * piece_mass);
+        let isometry = position.position;
+        let across = isometry.rotation * Vector::x();
+        commands.entity(entity).despawn_recursive();
+        for side in [-1., 1.] {
+            let position = Isometry::new(
+                isometry.translation.vector + across * size * side,
+                isometry.rotation.angle(),
+            );
+            let piece = spawn_shape(
+                &mut commands,
+                &converter,
+                &spawn_config,
+                &mut rng.0,
+                (shape.window, interior),
+                position.translation.vector.into(),
+                ShapeSpec { size, ..*spec },
+            );
+            commands
+                .entity(piece)
+                .insert(RigidBodyPositionComponent::from(RigidBodyPosition {
+                    position,
+                    next_position: position,
+                }))
+                .insert(RigidBodyVelocityComponent::from(RigidBodyVelocity {
+                    linvel: linvel + across * shatter.split_speed * side,
+                    angvel: velocity.angvel,
+                }));
+        }
+    }
+}
+
+pub(crate) fn expired_shapes_despawn(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut shapes: Query<(Entity, &mut Lifetime)>,
+) {
+    for (entity, mut lifetime) in shapes.iter_mut() {
+        lifetime.0 -= time.delta_seconds();
+        if lifetime.0 <= 0. {
+            // the trail pieces are children of the shape
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// What is done with a shape that has escaped its window, found more than `margin` physics units
+/// outside every monitor where it can never be seen again.
+pub struct EscapeConfig {
+    pub margin: Real,
+    pub action: EscapeAction,
+}
+
+pub enum EscapeAction {
+    Despawn,
+    /// put back at rest in the middle of its window
+    Teleport,
+}
+
+impl Default for EscapeConfig {
+    fn default() -> Self {
+        Self {
+            margin: 0.1,
+            action: EscapeAction::Despawn,
+        }
+    }
+}
+
+// fast flings and resizes can push shapes through their window's walls, after which they drift
+// off forever, so they are cleared up instead of piling up over a long session
+pub(crate) fn escaped_shapes_drain(
+    mut commands: Commands,
+    converter: Res<CoordConverter>,
+    escape: Res<EscapeConfig>,
+    mut shapes: Query<(
+        Entity,
+        &InteriorShape,
+        &mut RigidBodyPositionComponent,
+        &mut RigidBodyVelocityComponent,
+    )>,
+    windows: Query<&RigidBodyPositionComponent, (With<PhysicsWindow>, Without<InteriorShape>)>,
+) {
+    let margin = Vector::repeat(escape.margin);
+    for (entity, shape, mut position, mut velocity) in shapes.iter_mut() {
+        let p = position.position.translation.vector;
+        let on_a_monitor = converter.monitors.iter().any(|monitor| {
+            let [min, max] = converter.monitor_physics_bounds(monitor);
+            let (min, max) = (min.coords - margin, max.coords + margin);
+            (min.x..=max.x).contains(&p.x) && (min.y..=max.y).contains(&p.y)
+        });
+        if on_a_monitor {
+            continue;
+        }
+        match (&escape.action, windows.get(shape.window)) {
+            (EscapeAction::Teleport, Ok(window)) => {
+                position.position = Isometry::new(window.position.translation.vector, 0.);
+                position.next_position = position.position;
+                velocity.linvel = Vector::zeros();
+                velocity.angvel = 0.;
+            }
+            // also when its window is gone
+            _ => commands.entity(entity).despawn_recursive(),
+        }
+    }
+}
+
+/// How the shapes are coloured.
+pub enum ColorMode {
+    /// in the random colour each was spawned with
+    Static,
+    /// from blue at rest to red at `max_speed`, in physics units per second, and faster
+    Velocity { max_speed: Real },
+}
+
+impl Default for ColorMode {
+    fn default() -> Self {
+        Self::Static
+    }
+}
+
+const COOL: Color = Color::rgb(0.1, 0.3, 1.);
+const HOT: Color = Color::rgb(1., 0.15, 0.05);
+
+// the fill is only written when its colour changes, since lyon rebuilds the mesh of a changed shape
+pub(crate) fn colour_shapes(
+    color_mode: Res<ColorMode>,
+    mut shapes: Query<(&RigidBodyVelocityComponent, &BaseColour, &mut DrawMode)>,
+) {
+    for (velocity, base, mut mode) in shapes.iter_mut() {
+        let colour = match *color_mode {
+            ColorMode::Static => base.0,
+            ColorMode::Velocity { max_speed } => {
+                let t = (velocity.linvel.norm() / max_speed).min(1.);
+                let lerp = |cool: f32, hot: f32| cool + (hot - cool) * t;
+                Color::rgb(
+                    lerp(COOL.r(), HOT.r()),
+                    lerp(COOL.g(), HOT.g()),
+                    lerp(COOL.b(), HOT.b()),
+                )
+            }
+        };
+        if matches!(&*mode, DrawMode::Fill(fill) if fill.color != colour) {
+            if let DrawMode::Fill(fill) = &mut *mode {
+                fill.color = colour;
+            }
+        }
+    }
+}
+
+/// How the shapes are drawn. The colliders are the same either way.
+pub enum ShapeRender {
+    /// filled in their random colour
+    Lyon,
+    /// as one of the [`TextureConfig`] images, picked at random and stretched over the shape's
+    /// bounding box
+    Sprite,
+}
+
+impl Default for ShapeRender {
+    fn default() -> Self {
+        Self::Lyon
+    }
+}
+
+/// Images shapes are drawn with under [`ShapeRender::Sprite`], e.g. emoji or logos loaded with
+/// `AssetServer::load`. Empty by default, which keeps the shapes filled.
+#[derive(Default)]
+pub struct TextureConfig(pub Vec<Handle<Image>>);
+
+// every shape is spawned filled, wherever it comes from, and has its fill swapped for a sprite
+// here. The sprite keeps the shape's transform, which its body goes on moving and turning
+pub(crate) fn sprites_replace_fills(
+    mut commands: Commands,
+    shape_render: Res<ShapeRender>,
+    textures: Res<TextureConfig>,
+    converter: Res<CoordConverter>,
+    mut rng: ResMut<ShapeRng>,
+    shapes: Query<(Entity, &ColliderShapeComponent, &Transform), Added<InteriorShape>>,
+) {
+    use rand::seq::SliceRandom;
+
+    if !matches!(*shape_render, ShapeRender::Sprite) {
+        return;
+    }
+    if textures.0.is_empty() {
+        if shape_render.is_changed() {
+            warn!("ShapeRender::Sprite needs TextureConfig images, the shapes stay filled");
+        }
+        return;
+    }
+    for (entity, shape, transform) in shapes.iter() {
+        let texture = textures
+            .0
+            .choose(&mut rng.0)
+            .expect("textures is not empty")
+            .clone();
+        let half_extents = shape.compute_local_aabb().half_extents();
+        commands
+            .entity(entity)
+            .remove_bundle::<ShapeBundle>()
+            .insert_bundle(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(
+                        Vec2::new(half_extents.x, half_extents.y) * 2. * converter.physics_scale,
+                    ),
+                    ..Default::default()
+                },
+                texture,
+                transform: *transform,
+                ..Default::default()
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_single_nonzero_weight_always_picks_its_shape() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let weights = ShapeWeights {
+            circle: 0.,
+            square: 0.,
+            triangle: 3.,
+            star: 0.,
+        };
+        for _ in 0..100 {
+            assert_eq!(weights.pick(&mut rng), ShapeKind::Triangle);
+        }
+    }
+
+    #[test]
+    fn no_positive_weight_falls_back_to_circles() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let weights = ShapeWeights {
+            circle: 0.,
+            square: 0.,
+            triangle: 0.,
+            star: 0.,
+        };
+        assert_eq!(weights.pick(&mut rng), ShapeKind::Circle);
+    }
+
+    #[test]
+    fn zero_size_polygons_fall_back_to_balls() {
+        let points = star_points(3, 0., 0.);
+        let triangle = [points[0], points[2], points[4]];
+        assert!(triangle_collider(&triangle, 0.).as_ball().is_some());
+        assert!(star_collider(&star_points(5, 0., 0.), 0.)
+            .as_ball()
+            .is_some());
+        // and the usual sizes still get their polygons
+        let points = star_points(3, 1., 0.5);
+        let triangle = [points[0], points[2], points[4]];
+        assert!(triangle_collider(&triangle, 1.)
+            .as_convex_polygon()
+            .is_some());
+        assert!(star_collider(&star_points(5, 1., 0.45), 1.)
+            .as_compound()
+            .is_some());
+    }
+}
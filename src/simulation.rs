@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use bevy_rapier2d::{physics::SimulationToRenderTime, prelude::*};
+
+use crate::{replay::Recorder, AlwaysOnTop};
+
+/// Freezes the shapes and the window while true.
+#[derive(Default)]
+pub struct SimulationPaused(pub bool);
+
+pub(crate) fn toggle_pause_on_p(
+    keys: Res<Input<KeyCode>>,
+    mut paused: ResMut<SimulationPaused>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    if keys.just_pressed(KeyCode::P) {
+        paused.0 = !paused.0;
+        rapier_config.physics_pipeline_active = !paused.0;
+    }
+}
+
+/// One physics step asked for with `.` while paused, and the frame after it was taken, when the
+/// windows are moved to where it left them.
+pub(crate) enum StepRequest {
+    Idle,
+    Requested,
+    Stepped,
+}
+
+impl Default for StepRequest {
+    fn default() -> Self {
+        Self::Idle
+    }
+}
+
+impl StepRequest {
+    pub(crate) fn is_stepping(&self) -> bool {
+        !matches!(self, StepRequest::Idle)
+    }
+}
+
+/// Label of the system that asks for a single step, the system owing rapier time runs after it so
+/// the step is taken the same frame. Both run before rapier steps.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct StepControl;
+
+// the pipeline is switched on for a single frame, which is owed exactly one step. Not during a
+// replay, which keeps the pipeline off itself
+pub(crate) fn period_steps_paused_physics(
+    keys: Res<Input<KeyCode>>,
+    paused: Res<SimulationPaused>,
+    recorder: Res<Recorder>,
+    mut step: ResMut<StepRequest>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    *step = match *step {
+        StepRequest::Requested => {
+            rapier_config.physics_pipeline_active = !paused.0;
+            StepRequest::Stepped
+        }
+        StepRequest::Idle | StepRequest::Stepped => StepRequest::Idle,
+    };
+    if paused.0 && !recorder.is_playing() && keys.just_pressed(KeyCode::Period) {
+        rapier_config.physics_pipeline_active = true;
+        *step = StepRequest::Requested;
+    }
+}
+
+/// How fast simulated time passes relative to real time.
+pub struct TimeScale(pub Real);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl TimeScale {
+    const RANGE: std::ops::RangeInclusive<Real> = 0.1..=4.0;
+    const STEP: Real = 1.25;
+}
+
+pub(crate) fn plus_minus_change_time_scale(
+    keys: Res<Input<KeyCode>>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    let factor = if keys.just_pressed(KeyCode::Equals) || keys.just_pressed(KeyCode::NumpadAdd) {
+        TimeScale::STEP
+    } else if keys.just_pressed(KeyCode::Minus) || keys.just_pressed(KeyCode::NumpadSubtract) {
+        1. / TimeScale::STEP
+    } else {
+        return;
+    };
+    time_scale.0 =
+        (time_scale.0 * factor).clamp(*TimeScale::RANGE.start(), *TimeScale::RANGE.end());
+}
+
+/// Seconds of simulated time in every physics step, whatever the frame rate. A frame takes as
+/// many steps as the time since the last one holds, none at all on a fast monitor's in-between
+/// frames.
+pub const PHYSICS_DT: Real = 1. / 60.;
+
+// time owed past this many steps is dropped rather than caught up, after the event loop was held
+// up by the OS moving a window, say. It's this many per frame's worth of real time, so a sped up
+// time scale owes as many more and x4 still keeps up at 30 fps
+const MAX_STEPS_PER_FRAME: Real = 4.;
+
+/// How many physics steps rapier takes this frame, worked out along with the time owed to it.
+#[derive(Default)]
+pub(crate) struct StepsThisFrame(pub(crate) u32);
+
+/// Label of the system owing rapier time, it runs before rapier steps.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct OweTime;
+
+// in interpolated timestep mode rapier owes itself the frame's real time and steps by `dt` until
+// it's paid. The time scale is made up here, before it adds the frame's time. Nothing is owed
+// while the pipeline is off
+pub(crate) fn apply_time_scale(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    step: Res<StepRequest>,
+    rapier_config: Res<RapierConfiguration>,
+    mut integration_parameters: ResMut<IntegrationParameters>,
+    mut sim_to_render_time: ResMut<SimulationToRenderTime>,
+    mut steps: ResMut<StepsThisFrame>,
+) {
+    integration_parameters.dt = PHYSICS_DT;
+    steps.0 = 0;
+    if !rapier_config.physics_pipeline_active {
+        return;
+    }
+    let frame = time.delta_seconds();
+    // half a step more than the one asked for, rapier adding the frame's time back can round below
+    // `dt` and take none
+    let owed = if matches!(*step, StepRequest::Requested) {
+        1.5 * PHYSICS_DT
+    } else {
+        let max_owed = MAX_STEPS_PER_FRAME * PHYSICS_DT * time_scale.0.max(1.);
+        (sim_to_render_time.diff + frame * time_scale.0).min(max_owed)
+    };
+    sim_to_render_time.diff = owed - frame;
+
+    // counted the way rapier pays it, rounding and all
+    let mut left = sim_to_render_time.diff + frame;
+    while left >= PHYSICS_DT {
+        steps.0 += 1;
+        left -= PHYSICS_DT;
+    }
+}
+
+pub(crate) fn title_shows_status(
+    time_scale: Res<TimeScale>,
+    always_on_top: Res<AlwaysOnTop>,
+    mut windows: ResMut<Windows>,
+) {
+    if time_scale.is_changed() || always_on_top.is_changed() {
+        let mut title = "window.velocity".to_string();
+        if (time_scale.0 - 1.).abs() >= 0.01 {
+            title += &format!(" (x{:.2})", time_scale.0);
+        }
+        if always_on_top.0 {
+            title += " [on top]";
+        }
+        // there's none when headless
+        if let Some(window) = windows.get_primary_mut() {
+            window.set_title(title);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{MonitorBounds, WindowState};
+
+/// Loudness of the sound played when the window hits a monitor edge.
+pub struct SoundConfig {
+    pub master_volume: Real, // between 0 and 1
+    pub muted: bool,
+    pub full_volume_speed: Real, // impact speed played at master volume, physics units per second
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            muted: false,
+            full_volume_speed: 2.0,
+        }
+    }
+}
+
+// the same bounce at increasing loudness, since bevy's Audio can't set the volume of a sound
+pub(crate) struct BounceSounds(Vec<Handle<AudioSource>>);
+
+pub(crate) fn load_bounce_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BounceSounds(
+        (1..=3)
+            .map(|i| asset_server.load(&format!("sounds/bounce-{}.wav", i)))
+            .collect(),
+    ));
+}
+
+// impacts this soon after a bounce was heard are ignored, so a window settling in a corner
+// doesn't rattle
+const BOUNCE_SOUND_DEBOUNCE: f64 = 0.05;
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn window_hitting_monitor_plays_sound(
+    time: Res<Time>,
+    mut contact_events: EventReader<ContactEvent>,
+    window_query: Query<&RigidBodyVelocityComponent, With<WindowState>>,
+    monitors: Query<Entity, With<MonitorBounds>>,
+    audio: Res<Audio>,
+    sounds: Res<BounceSounds>,
+    sound_config: Res<SoundConfig>,
+    mut last_played: Local<f64>,
+) {
+    for event in contact_events.iter() {
+        let (e1, e2) = match event {
+            ContactEvent::Started(h1, h2) => (h1.entity(), h2.entity()),
+            ContactEvent::Stopped(_, _) => continue,
+        };
+        let velocity = match window_query.get(e1).or_else(|_| window_query.get(e2)) {
+            Ok(velocity) => velocity,
+            Err(_) => continue,
+        };
+        if monitors.get(e1).is_err() && monitors.get(e2).is_err() {
+            continue;
+        }
+
+        let now = time.seconds_since_startup();
+        if sound_config.muted || now - *last_played < BOUNCE_SOUND_DEBOUNCE {
+            continue;
+        }
+
+        let loudness = sound_config.master_volume
+            * (velocity.linvel.norm() / sound_config.full_volume_speed).min(1.);
+        let level = (loudness * sounds.0.len() as Real).ceil() as usize;
+        if level == 0 {
+            continue;
+        }
+        audio.play(sounds.0[level.min(sounds.0.len()) - 1].clone());
+        *last_played = now;
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    groups::{MONITOR_BOUNDS, WINDOW_BODY},
+    shapes::InteriorShape,
+};
+
+/// Collisions counted since startup, or since they were last reset with Delete.
+#[derive(Default)]
+pub struct CollisionStats {
+    pub total: u64,
+    pub window_edge: u64, // a window hitting a monitor edge
+    pub shape_shape: u64,
+}
+
+// what a collider is, from its collision groups. The walls share their window's group with its
+// shapes, so only the InteriorShape marker tells the two apart
+enum Category {
+    Window,
+    Monitor,
+    Shape,
+    Other,
+}
+
+fn category(flags: &ColliderFlags, is_shape: bool) -> Category {
+    match flags.collision_groups.memberships {
+        // monitors belong to every group, so this is checked before WINDOW_BODY alone
+        MONITOR_BOUNDS => Category::Monitor,
+        WINDOW_BODY => Category::Window,
+        _ if is_shape => Category::Shape,
+        _ => Category::Other,
+    }
+}
+
+// only colliders with contact events enabled, the windows and the shapes, report collisions
+pub(crate) fn count_collisions(
+    mut contact_events: EventReader<ContactEvent>,
+    colliders: Query<(&ColliderFlagsComponent, Option<&InteriorShape>)>,
+    mut stats: ResMut<CollisionStats>,
+) {
+    for event in contact_events.iter() {
+        let (h1, h2) = match event {
+            ContactEvent::Started(h1, h2) => (h1, h2),
+            ContactEvent::Stopped(_, _) => continue,
+        };
+        let categorise = |handle: &ColliderHandle| {
+            colliders
+                .get(handle.entity())
+                .map_or(Category::Other, |(flags, shape)| {
+                    category(flags, shape.is_some())
+                })
+        };
+        stats.total += 1;
+        match (categorise(h1), categorise(h2)) {
+            (Category::Window, Category::Monitor) | (Category::Monitor, Category::Window) => {
+                stats.window_edge += 1
+            }
+            (Category::Shape, Category::Shape) => stats.shape_shape += 1,
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn delete_resets_collision_stats(
+    keys: Res<Input<KeyCode>>,
+    mut stats: ResMut<CollisionStats>,
+) {
+    if keys.just_pressed(KeyCode::Delete) {
+        *stats = CollisionStats::default();
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    shapes::{BaseColour, InteriorShape},
+    simulation::SimulationPaused,
+};
+
+/// Trails drawn behind the shapes. A `length` of zero turns them off.
+pub struct TrailConfig {
+    pub length: usize, // in frames
+    pub fade: f32,     // opacity of the newest end of a trail, the oldest end is transparent
+}
+
+impl Default for TrailConfig {
+    fn default() -> Self {
+        Self {
+            length: 0,
+            fade: 0.5,
+        }
+    }
+}
+
+// a trail is drawn in this many pieces, each older one more transparent
+const TRAIL_PIECES: usize = 4;
+
+#[derive(Component)]
+pub(crate) struct Trail {
+    colour: Color,
+    points: VecDeque<Vec2>, // oldest first, in world coordinates
+    pieces: Vec<Entity>,    // children of the shape, oldest first
+}
+
+impl Trail {
+    pub(crate) fn new(colour: Color) -> Self {
+        Self {
+            colour,
+            points: VecDeque::new(),
+            pieces: Vec::new(),
+        }
+    }
+}
+
+pub(crate) fn draw_trails(
+    mut commands: Commands,
+    trail_config: Res<TrailConfig>,
+    paused: Res<SimulationPaused>,
+    mut trails: Query<(Entity, &Transform, &mut Trail)>,
+    mut paths: Query<&mut Path>,
+) {
+    for (entity, transform, mut trail) in trails.iter_mut() {
+        let trail = &mut *trail;
+        if paused.0 || trail_config.length == 0 {
+            trail.points.clear();
+        } else {
+            trail.points.push_back(transform.translation.truncate());
+            while trail.points.len() > trail_config.length {
+                trail.points.pop_front();
+            }
+        }
+
+        if trail.pieces.is_empty() {
+            if trail.points.is_empty() {
+                continue;
+            }
+            for i in 0..TRAIL_PIECES {
+                let mut colour = trail.colour;
+                colour.set_a(trail_config.fade * (i + 1) as f32 / TRAIL_PIECES as f32);
+                let piece = commands
+                    .spawn_bundle(GeometryBuilder::build_as(
+                        &shapes::Line(Vec2::ZERO, Vec2::ZERO),
+                        DrawMode::Stroke(StrokeMode::new(colour, 2.)),
+                        Transform::from_xyz(0., 0., -0.1),
+                    ))
+                    .id();
+                commands.entity(entity).add_child(piece);
+                trail.pieces.push(piece);
+            }
+        }
+
+        // the pieces are children of the shape, so undo its movement and rotation
+        let to_local = transform.compute_matrix().inverse();
+        let points: Vec<Vec2> = trail
+            .points
+            .iter()
+            .map(|p| to_local.transform_point3(p.extend(0.)).truncate())
+            .collect();
+        let per_piece = (points.len().saturating_sub(1) + TRAIL_PIECES - 1) / TRAIL_PIECES;
+        for (i, &piece) in trail.pieces.iter().enumerate() {
+            // neighbouring pieces share a point so there are no gaps between them
+            let start = (i * per_piece).min(points.len());
+            let end = ((i + 1) * per_piece + 1).min(points.len());
+            let mut builder = PathBuilder::new();
+            if end > start + 1 {
+                builder.move_to(points[start]);
+                for &p in &points[start + 1..end] {
+                    builder.line_to(p);
+                }
+            }
+            if let Ok(mut path) = paths.get_mut(piece) {
+                *path = builder.build();
+            }
+        }
+    }
+}
+
+/// Shapes leaving a dot of their colour wherever they are every `interval` seconds while
+/// `enabled`, toggled with 9, slowly painting the windows. Unlike trails the dots stay, up to
+/// `max_dots`, past which the oldest are painted over somewhere new. Backspace clears them all.
+/// Off by default.
+pub struct PaintMode {
+    pub enabled: bool,
+    pub interval: f32,
+    pub radius: f32, // pixels
+    pub max_dots: usize,
+}
+
+impl Default for PaintMode {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: 0.1,
+            radius: 2.,
+            max_dots: 5000,
+        }
+    }
+}
+
+/// The dots painted so far, oldest first.
+#[derive(Default)]
+pub(crate) struct Canvas(VecDeque<Entity>);
+
+pub(crate) fn keys_control_paint(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut paint: ResMut<PaintMode>,
+    mut canvas: ResMut<Canvas>,
+) {
+    if keys.just_pressed(KeyCode::Key9) {
+        paint.enabled = !paint.enabled;
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        for dot in canvas.0.drain(..) {
+            commands.entity(dot).despawn();
+        }
+    }
+}
+
+// dots are plain lyon shapes with no body or collider. Once there are `max_dots` the oldest one
+// is moved and recoloured instead of spawning another
+pub(crate) fn shapes_paint_dots(
+    mut commands: Commands,
+    time: Res<Time>,
+    paint: Res<PaintMode>,
+    paused: Res<SimulationPaused>,
+    mut since_last: Local<f32>,
+    mut canvas: ResMut<Canvas>,
+    painters: Query<(&GlobalTransform, &BaseColour), With<InteriorShape>>,
+    mut dots: Query<(&mut Transform, &mut DrawMode)>,
+) {
+    if !paint.enabled || paused.0 {
+        return;
+    }
+    *since_last += time.delta_seconds();
+    if *since_last < paint.interval {
+        return;
+    }
+    *since_last = 0.;
+
+    // the cap may have been lowered since
+    while canvas.0.len() > paint.max_dots.max(1) {
+        if let Some(oldest) = canvas.0.pop_front() {
+            commands.entity(oldest).despawn();
+        }
+    }
+
+    for (shape, colour) in painters.iter() {
+        // behind the shapes, in front of their trails
+        let transform = Transform::from_translation(shape.translation.truncate().extend(-0.05));
+        if canvas.0.len() >= paint.max_dots.max(1) {
+            if let Some(oldest) = canvas.0.pop_front() {
+                if let Ok((mut dot_transform, mut mode)) = dots.get_mut(oldest) {
+                    *dot_transform = transform;
+                    *mode = DrawMode::Fill(FillMode::color(colour.0));
+                    canvas.0.push_back(oldest);
+                    continue;
+                }
+            }
+        }
+        let dot = commands
+            .spawn_bundle(GeometryBuilder::build_as(
+                &shapes::Circle {
+                    radius: paint.radius,
+                    center: Vec2::ZERO,
+                },
+                DrawMode::Fill(FillMode::color(colour.0)),
+                transform,
+            ))
+            .id();
+        canvas.0.push_back(dot);
+    }
+}
@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{shapes::InteriorShape, PhysicsWindow, WindowState};
+
+/// A steady push on the bouncing windows and every shape. Unlike gravity it's the same force
+/// whatever a body weighs, so it blows the light shapes about far more than the windows.
+///
+/// I, J, K and L strengthen it by `step` upwards, left, down and right. It swells and drops by up
+/// to `gustiness` of its strength, once every `gust_period` seconds.
+pub struct Wind {
+    pub force: Vector<Real>,
+    pub step: Real,
+    pub gustiness: Real,
+    pub gust_period: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            force: Vector::zeros(),
+            step: 0.0002,
+            gustiness: 0.5,
+            gust_period: 3.,
+        }
+    }
+}
+
+/// The arrow in the top right corner showing which way the wind blows.
+#[derive(Component)]
+pub(crate) struct WindIndicator;
+
+pub(crate) fn ijkl_change_wind(keys: Res<Input<KeyCode>>, mut wind: ResMut<Wind>) {
+    let direction = if keys.just_pressed(KeyCode::I) {
+        Vector::y()
+    } else if keys.just_pressed(KeyCode::K) {
+        -Vector::y()
+    } else if keys.just_pressed(KeyCode::J) {
+        -Vector::x()
+    } else if keys.just_pressed(KeyCode::L) {
+        Vector::x()
+    } else {
+        return;
+    };
+    let step = wind.step;
+    wind.force += direction * step;
+}
+
+pub(crate) fn wind_pushes_bodies(
+    time: Res<Time>,
+    wind: Res<Wind>,
+    mut windows: Query<
+        (
+            &WindowState,
+            &mut RigidBodyForcesComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        (With<PhysicsWindow>, Without<InteriorShape>),
+    >,
+    mut shapes: Query<
+        (
+            &mut RigidBodyForcesComponent,
+            &mut RigidBodyActivationComponent,
+        ),
+        With<InteriorShape>,
+    >,
+) {
+    if wind.force == Vector::zeros() {
+        return;
+    }
+    let phase = time.seconds_since_startup() as f32 * std::f32::consts::TAU / wind.gust_period;
+    let force = wind.force * (1. + wind.gustiness * phase.sin());
+
+    for (state, mut forces, mut activation) in windows.iter_mut() {
+        if let WindowState::Bouncing = state {
+            forces.force += force;
+            activation.wake_up(true);
+        }
+    }
+    for (mut forces, mut activation) in shapes.iter_mut() {
+        forces.force += force;
+        activation.wake_up(true);
+    }
+}
+
+pub(crate) fn spawn_wind_indicator(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(5.),
+                    right: Val::Px(5.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/DejaVuSansMono.ttf"),
+                    font_size: 16.,
+                    color: Color::WHITE,
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(WindIndicator);
+}
+
+// the arrow points the nearest of eight ways, it's blank while there's no wind
+pub(crate) fn show_wind(wind: Res<Wind>, mut indicator: Query<&mut Text, With<WindIndicator>>) {
+    const ARROWS: [char; 8] = ['→', '↗', '↑', '↖', '←', '↙', '↓', '↘'];
+
+    if !wind.is_changed() {
+        return;
+    }
+    let value = if wind.force == Vector::zeros() {
+        String::new()
+    } else {
+        let angle = wind.force.y.atan2(wind.force.x);
+        let eighth = (angle / std::f32::consts::FRAC_PI_4).round() as i32;
+        format!("wind {}", ARROWS[eighth.rem_euclid(8) as usize])
+    };
+    for mut text in indicator.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
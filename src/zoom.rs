@@ -0,0 +1,111 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+
+use crate::{coords::CoordConverter, PhysicsWindow};
+
+// projection scales, below 1 zooms in
+const ZOOM_RANGE: std::ops::RangeInclusive<f32> = 0.25..=4.;
+// scale change per line scrolled
+const ZOOM_STEP: f32 = 1.1;
+// pixels scrolled on a touchpad that count as a line
+const PIXELS_PER_LINE: f32 = 50.;
+
+/// How far a window's camera is zoomed in, as a projection scale: below 1 zooms in. The
+/// projection's scale is this times the monitor's, see camera_matches_monitor_scale.
+#[derive(Component)]
+pub(crate) struct CameraZoom(pub(crate) f32);
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+// lines scrolled up since the last frame, down is negative
+pub(crate) fn scrolled_lines(wheel_events: &mut EventReader<MouseWheel>) -> f32 {
+    wheel_events
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        })
+        .sum()
+}
+
+pub(crate) fn shift_held(keys: &Input<KeyCode>) -> bool {
+    keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift)
+}
+
+// the camera of the window the cursor is over is zoomed. Only the projection is scaled, the camera
+// keeps following its window's body through its transform, so the zoom stays centred on the window
+// whether it's static, dragged or bouncing
+pub(crate) fn mouse_wheel_zooms_window(
+    mut wheel_events: EventReader<MouseWheel>,
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    window_query: Query<&PhysicsWindow>,
+    mut cameras: Query<(&Parent, &mut CameraZoom)>,
+) {
+    let lines = scrolled_lines(&mut wheel_events);
+    // shift and the wheel add and remove shapes instead
+    if lines == 0. || shift_held(&keys) {
+        return;
+    }
+    for (parent, mut zoom) in cameras.iter_mut() {
+        let hovered = window_query
+            .get(parent.0)
+            .ok()
+            .and_then(|physics_window| windows.get(physics_window.id))
+            .map_or(false, |window| window.cursor_position().is_some());
+        if hovered {
+            zoom.0 =
+                (zoom.0 * ZOOM_STEP.powf(-lines)).clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end());
+        }
+    }
+}
+
+pub(crate) fn home_resets_zoom(
+    keys: Res<Input<KeyCode>>,
+    window_query: Query<&PhysicsWindow>,
+    mut cameras: Query<(&Parent, &mut CameraZoom)>,
+) {
+    if !keys.just_pressed(KeyCode::Home) {
+        return;
+    }
+    for (parent, mut zoom) in cameras.iter_mut() {
+        if window_query.get(parent.0).is_ok() {
+            *zoom = CameraZoom::default();
+        }
+    }
+}
+
+// bevy draws a world unit as one of the window's logical pixels, which is `scale_factor` physical
+// pixels wherever the window is, while the shapes are sized in desktop units, which are always
+// `desktop_scale` physical pixels. On a monitor whose scale factor differs from the desktop's, the
+// projection is scaled by the difference, so the shapes keep their size on screen and still fill
+// the window as they cross from one monitor to another. PhysicsScale is untouched, it maps physics
+// units to desktop units, and this only corrects how those are drawn
+pub(crate) fn camera_matches_monitor_scale(
+    windows: Res<Windows>,
+    converter: Res<CoordConverter>,
+    window_query: Query<&PhysicsWindow>,
+    mut cameras: Query<(&Parent, &CameraZoom, &mut OrthographicProjection)>,
+) {
+    for (parent, zoom, mut projection) in cameras.iter_mut() {
+        let window = match window_query
+            .get(parent.0)
+            .ok()
+            .and_then(|physics_window| windows.get(physics_window.id))
+        {
+            Some(window) => window,
+            None => continue,
+        };
+        let scale = zoom.0 * (window.scale_factor() / converter.desktop_scale) as f32;
+        // assigning it every frame would mark the projection changed, and bevy recomputes it then
+        if (projection.scale - scale).abs() > f32::EPSILON {
+            projection.scale = scale;
+        }
+    }
+}